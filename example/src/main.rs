@@ -2,12 +2,22 @@ use std::sync::Arc;
 
 use winit::{
     dpi::PhysicalPosition,
-    event::{ElementState, MouseButton, MouseScrollDelta, TouchPhase},
+    event::{ElementState, Modifiers, MouseButton, MouseScrollDelta, TouchPhase},
     event_loop::ActiveEventLoop,
-    keyboard::Key,
-    window::Window,
+    window::{Window, WindowAttributes},
+};
+use y_engine::{
+    app::App,
+    error::EngineError,
+    event::EventProxy,
+    startup::StartupResults,
+    state::common::render::RenderCore,
+    util::{
+        input::{InputManager, KeyInput},
+        time::FrameTime,
+    },
+    YEngine,
 };
-use y_engine::{app::App, state::common::render::RenderCore, util::input::InputManager, YEngine};
 
 struct MyApp {
     _window: Arc<Window>,
@@ -16,11 +26,14 @@ struct MyApp {
 }
 
 impl App for MyApp {
-    fn new(window: Arc<Window>, render_core: RenderCore) -> Box<Self> {
-        window.set_resizable(true);
-        window.set_decorations(true);
-        window.set_title("Y-ENGINE EXAMPLE");
-
+    type UserEvent = ();
+
+    fn new(
+        window: Arc<Window>,
+        render_core: RenderCore,
+        _: StartupResults,
+        _: EventProxy<()>,
+    ) -> Box<Self> {
         Box::new(MyApp {
             _window: window,
             render_core,
@@ -28,34 +41,33 @@ impl App for MyApp {
         })
     }
 
-    fn window_resized(&mut self, width: u32, height: u32) {
-        if width > 0 && height > 0 {
-            self.render_core.surface_config.width = width;
-            self.render_core.surface_config.height = height;
-            self.render_core
-                .surface
-                .configure(&self.render_core.device, &self.render_core.surface_config);
-        }
+    fn startup_failed(error: &EngineError) {
+        eprintln!("Y-ENGINE EXAMPLE failed to start: {error}");
     }
 
     fn window_close_requested(&mut self, event_loop: &ActiveEventLoop) {
         event_loop.exit();
     }
 
-    fn window_redraw(&mut self) {
+    fn window_redraw(&mut self, _: &FrameTime) {
         match self.render() {
             Ok(_) => {}
             Err(wgpu::SurfaceError::OutOfMemory) => panic!("Out of memory"),
             _ => {}
         }
+        self.input_manager.end_frame();
     }
 
     fn mouse_button_input(&mut self, button: MouseButton, state: ElementState) {
         self.input_manager.handle_mouse_button_input(button, state);
     }
 
-    fn keyboard_button_input(&mut self, key: Key, state: ElementState) {
-        self.input_manager.handle_keyboard_button_input(key, state);
+    fn keyboard_button_input(&mut self, input: KeyInput) {
+        self.input_manager.handle_keyboard_button_input(input);
+    }
+
+    fn modifiers_changed(&mut self, modifiers: Modifiers) {
+        self.input_manager.handle_modifiers_changed(modifiers);
     }
 
     fn mouse_wheel_input(&mut self, delta: MouseScrollDelta, phase: TouchPhase) {
@@ -73,12 +85,16 @@ impl App for MyApp {
     fn cursor_left_window(&mut self) {
         self.input_manager.handle_cursor_left_window();
     }
+
+    fn raw_mouse_motion(&mut self, dx: f64, dy: f64) {
+        self.input_manager.handle_raw_mouse_motion(dx, dy);
+    }
 }
 
 impl MyApp {
     fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
         let rc = &self.render_core;
-        let output = rc.surface.get_current_texture()?;
+        let output = rc.acquire_frame()?;
         let view = output
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
@@ -117,6 +133,10 @@ impl MyApp {
 }
 
 fn main() {
-    let mut yengine: YEngine<MyApp> = YEngine::default();
-    yengine.run();
+    let mut yengine: YEngine<MyApp> = YEngine::builder()
+        .window(WindowAttributes::default().with_title("Y-ENGINE EXAMPLE"))
+        .build();
+    if yengine.run().is_err() {
+        std::process::exit(1);
+    }
 }
@@ -1,38 +1,167 @@
-use std::sync::Arc;
+use std::{path::Path, sync::Arc, time::Duration};
 
 use winit::{
     dpi::PhysicalPosition,
-    event::{ElementState, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent},
-    event_loop::ActiveEventLoop,
-    keyboard::Key,
-    window::Window,
+    event::{
+        AxisId, ButtonId, DeviceEvent, DeviceId, ElementState, Ime, InnerSizeWriter, Modifiers,
+        MouseButton, MouseScrollDelta, StartCause, Touch, TouchPhase, WindowEvent,
+    },
+    event_loop::{ActiveEventLoop, AsyncRequestSerial},
+    keyboard::PhysicalKey,
+    window::{ActivationToken, Theme, Window, WindowId},
 };
 
-use crate::state::common::render::RenderCore;
+use crate::{
+    error::EngineError,
+    event::EventProxy,
+    startup::{StartupResults, StartupTasks},
+    state::common::render::RenderCore,
+    util::{cursor::CursorGrab, input::KeyInput, time::FrameTime},
+};
 
 #[allow(unused_variables)]
 pub trait App {
-    fn new(window: Arc<Window>, render_core: RenderCore) -> Box<Self>
+    /// Events the app sends itself through its [`EventProxy`], e.g. from background threads.
+    /// Use `()` if you don't need any.
+    type UserEvent: Send + 'static;
+
+    /// Called once the gpu is initialized and all startup tasks are done.
+    /// `startup_results` holds the results of the tasks added in [`App::startup_tasks`].
+    fn new(
+        window: Arc<Window>,
+        render_core: RenderCore,
+        startup_results: StartupResults,
+        proxy: EventProxy<Self::UserEvent>,
+    ) -> Box<Self>
     where
         Self: Sized;
 
+    /// Add tasks that run on background threads while the splash screen is shown,
+    /// so heavy loading (assets, configs, shaders) never happens on the event loop.
+    fn startup_tasks(tasks: &mut StartupTasks)
+    where
+        Self: Sized,
+    {
+    }
+
+    /// Called if the engine could not start up (no GPU, no window, etc.), instead of [`App::new`].
+    /// The error is also returned from [`crate::YEngine::run`].
+    fn startup_failed(error: &EngineError)
+    where
+        Self: Sized,
+    {
+    }
+
+    // Lifecycle events
+    /// Called at the start of every event loop iteration.
+    fn new_events(&mut self, cause: StartCause, winit_event_loop: &ActiveEventLoop) {}
+    /// Called at the end of every event loop iteration, after all events were handled.
+    fn about_to_wait(&mut self, winit_event_loop: &ActiveEventLoop) {}
+    /// The app was suspended by the os (e.g. sent to the background on mobile).
+    /// Release gpu resources you can recreate here.
+    fn suspended(&mut self, winit_event_loop: &ActiveEventLoop) {}
+    /// The app was resumed after [`App::suspended`]. Not called for the first start,
+    /// that is [`App::new`].
+    fn resumed(&mut self, winit_event_loop: &ActiveEventLoop) {}
+    /// An event sent through the [`EventProxy`] handed to [`App::new`].
+    fn user_event(&mut self, event: Self::UserEvent, winit_event_loop: &ActiveEventLoop) {}
+    /// The os is running low on memory (mobile only), free what you can.
+    fn memory_warning(&mut self, winit_event_loop: &ActiveEventLoop) {}
+    /// The event loop is exiting, e.g. save state here. The app is dropped right after,
+    /// before the render core's window surface and the window.
+    fn exiting(&mut self, winit_event_loop: &ActiveEventLoop) {}
+
     // Window events
     /// Runs before any other window event handlers.
     /// Return true if the event was consumed and should not be passed to the next handler.
-    fn window_raw_before(&mut self, event: &WindowEvent, winit_event_loop: &ActiveEventLoop) -> bool {
+    fn window_raw_before(
+        &mut self,
+        event: &WindowEvent,
+        winit_event_loop: &ActiveEventLoop,
+    ) -> bool {
         false
     }
     /// Runs after all other window event handlers.
     fn window_raw_after(&mut self, event: WindowEvent, winit_event_loop: &ActiveEventLoop) {}
+    /// The render core's surface is already resized when this is called.
+    /// Zero sizes mean the window was minimized, redraws are paused until it is restored.
     fn window_resized(&mut self, width: u32, height: u32) {}
     fn window_close_requested(&mut self, winit_event_loop: &ActiveEventLoop) {}
-    fn window_redraw(&mut self) {}
+    fn window_destroyed(&mut self) {}
+    fn window_moved(&mut self, position: PhysicalPosition<i32>) {}
+    fn window_focused(&mut self) {}
+    fn window_unfocused(&mut self) {}
+    /// Return the app's cursor grab, if any, to have the engine release it while the window
+    /// is unfocused and reacquire it with the next click.
+    fn cursor_grab(&mut self) -> Option<&mut CursorGrab> {
+        None
+    }
+    /// True if the window is completely hidden (e.g. behind other windows or on another
+    /// workspace), a good time to stop rendering.
+    fn window_occluded(&mut self, occluded: bool) {}
+    /// A `Resized` event follows, `inner_size_writer` can be used to pick a different new size.
+    fn window_scale_factor_changed(
+        &mut self,
+        scale_factor: f64,
+        inner_size_writer: InnerSizeWriter,
+    ) {
+    }
+    fn window_theme_changed(&mut self, theme: Theme) {}
+    fn window_activation_token_done(&mut self, serial: AsyncRequestSerial, token: ActivationToken) {
+    }
+    /// All events of windows created by the app, see
+    /// [`RenderCore::create_window_surface`]. The hooks above are only called for the main window.
+    fn secondary_window_event(
+        &mut self,
+        window_id: WindowId,
+        event: WindowEvent,
+        winit_event_loop: &ActiveEventLoop,
+    ) {
+    }
+    fn file_dropped(&mut self, path: &Path) {}
+    fn file_hovered(&mut self, path: &Path) {}
+    fn file_hover_cancelled(&mut self) {}
+    /// Called at the fixed rate of [`crate::config::LoopConfig::tick_rate`], independent of the
+    /// frame rate: zero or more times per frame, before [`App::update`].
+    /// Put deterministic simulation (physics, networking) here.
+    fn fixed_update(&mut self, step: Duration) {}
+    /// Called before every [`App::window_redraw`] with the time since the last frame.
+    /// How often frames happen is set by [`crate::config::LoopConfig`].
+    fn update(&mut self, dt: Duration) {}
+    fn window_redraw(&mut self, frame: &FrameTime) {}
 
     // User input events
+    /// Not called for the click that reacquires the [`App::cursor_grab`].
     fn mouse_button_input(&mut self, button: MouseButton, state: ElementState) {}
     fn mouse_wheel_input(&mut self, delta: MouseScrollDelta, phase: TouchPhase) {}
     fn cursor_moved(&mut self, position: PhysicalPosition<f64>) {}
     fn cursor_entered_window(&mut self) {}
     fn cursor_left_window(&mut self) {}
-    fn keyboard_button_input(&mut self, key: Key, state: ElementState) {}
+    /// Pressed keys are repeated while they are held, see [`KeyInput::repeat`].
+    fn keyboard_button_input(&mut self, input: KeyInput) {}
+    fn modifiers_changed(&mut self, modifiers: Modifiers) {}
+    /// Text input from an input method editor, see [`Window::set_ime_allowed`].
+    fn ime(&mut self, ime: Ime) {}
+    fn touch(&mut self, touch: Touch) {}
+    fn pinch_gesture(&mut self, delta: f64, phase: TouchPhase) {}
+    fn pan_gesture(&mut self, delta: PhysicalPosition<f32>, phase: TouchPhase) {}
+    fn rotation_gesture(&mut self, delta: f32, phase: TouchPhase) {}
+    fn double_tap_gesture(&mut self) {}
+    fn touchpad_pressure(&mut self, pressure: f32, stage: i64) {}
+    fn axis_motion(&mut self, axis: AxisId, value: f64) {}
+
+    // Device events
+    // Raw input from the devices themselves, not tied to the window or clamped to the screen.
+    // By default, winit only reports them while the window is focused.
+    /// Runs before the device event handlers below.
+    fn device_raw(&mut self, device_id: DeviceId, event: &DeviceEvent) {}
+    fn device_added(&mut self, device_id: DeviceId) {}
+    fn device_removed(&mut self, device_id: DeviceId) {}
+    /// Unaccelerated mouse movement, also reported while the cursor is grabbed.
+    /// Use it for mouselook instead of [`App::cursor_moved`].
+    fn raw_mouse_motion(&mut self, dx: f64, dy: f64) {}
+    fn raw_mouse_wheel(&mut self, delta: MouseScrollDelta) {}
+    fn raw_axis_motion(&mut self, axis: AxisId, value: f64) {}
+    fn raw_button_input(&mut self, button: ButtonId, state: ElementState) {}
+    fn raw_key_input(&mut self, key: PhysicalKey, state: ElementState) {}
 }
@@ -0,0 +1,279 @@
+use std::{borrow::Cow, fmt, path::PathBuf, sync::Arc, time::Duration};
+
+use image::RgbaImage;
+use wgpu::{
+    AdapterInfo, Backends, CompositeAlphaMode, Features, InstanceFlags, Limits, MemoryHints,
+    PowerPreference, PresentMode,
+};
+use winit::{
+    dpi::PhysicalSize,
+    window::{Window, WindowAttributes},
+};
+
+use crate::error::EngineError;
+
+const DEFAULT_SPLASH_IMAGE: &[u8] = include_bytes!("../assets/splashscreen.png");
+
+/// Everything that is configurable about how the engine starts up.
+///
+/// Build it directly or via [`crate::YEngine::builder`].
+#[derive(Debug, Clone)]
+pub struct EngineConfig {
+    /// Attributes applied to the window once the splash screen is done
+    /// and the app starts running.
+    pub window: WindowAttributes,
+    pub gpu: GpuConfig,
+    pub splash: SplashConfig,
+    pub game_loop: LoopConfig,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            window: WindowAttributes::default().with_title("Y-ENGINE"),
+            gpu: GpuConfig::default(),
+            splash: SplashConfig::default(),
+            game_loop: LoopConfig::default(),
+        }
+    }
+}
+
+/// Options for the wgpu instance, adapter, device and surface.
+///
+/// Adapters are picked like this: all adapters of the enabled backends that support the
+/// required features and limits (and the window surface) are candidates. If an
+/// `adapter_selector` is set, it may pick one of them. Otherwise they are tried in the order
+/// of `power_preference` (e.g. discrete, then integrated gpus), with software adapters last.
+#[derive(Debug, Clone)]
+pub struct GpuConfig {
+    pub backends: Backends,
+    pub instance_flags: InstanceFlags,
+    pub power_preference: PowerPreference,
+    /// Only consider software (cpu) adapters.
+    pub force_fallback_adapter: bool,
+    /// Consider software (cpu) adapters if no hardware adapter is suitable.
+    pub allow_fallback_adapter: bool,
+    pub adapter_selector: Option<AdapterSelector>,
+    /// Startup fails with [`crate::error::EngineError::MissingFeatures`] if these are not supported.
+    pub required_features: Features,
+    /// Enabled if the adapter supports them, see [`crate::state::common::render::RenderCore::features`].
+    pub optional_features: Features,
+    /// Startup fails with [`crate::error::EngineError::MissingLimits`] if these are not supported.
+    pub required_limits: Limits,
+    /// Requested as far as the adapter supports them (but never worse than `required_limits`),
+    /// see [`crate::state::common::render::RenderCore::limits`].
+    pub optional_limits: Limits,
+    pub memory_hints: MemoryHints,
+    /// Initial present mode, falls back to the closest supported one.
+    /// Can be changed at runtime with [`crate::state::common::render::RenderCore::set_present_mode`].
+    pub present_mode: PresentMode,
+    pub alpha_mode: CompositeAlphaMode,
+    pub desired_maximum_frame_latency: u32,
+}
+
+impl Default for GpuConfig {
+    fn default() -> Self {
+        Self {
+            backends: Backends::PRIMARY,
+            instance_flags: InstanceFlags::debugging(),
+            power_preference: PowerPreference::HighPerformance,
+            force_fallback_adapter: false,
+            allow_fallback_adapter: true,
+            adapter_selector: None,
+            required_features: Features::empty(),
+            optional_features: Features::empty(),
+            required_limits: Limits::default(),
+            optional_limits: Limits::default(),
+            memory_hints: MemoryHints::default(),
+            present_mode: PresentMode::AutoVsync,
+            alpha_mode: CompositeAlphaMode::Auto,
+            desired_maximum_frame_latency: 2,
+        }
+    }
+}
+
+/// Lets the app pick the adapter. Receives the infos of all suitable adapters
+/// and returns the index of the one to use, or None to use the default order.
+#[derive(Clone)]
+pub struct AdapterSelector(Arc<SelectorFn>);
+
+type SelectorFn = dyn Fn(&[AdapterInfo]) -> Option<usize> + Send + Sync;
+
+impl AdapterSelector {
+    pub fn new(selector: impl Fn(&[AdapterInfo]) -> Option<usize> + Send + Sync + 'static) -> Self {
+        Self(Arc::new(selector))
+    }
+
+    pub fn select(&self, adapters: &[AdapterInfo]) -> Option<usize> {
+        (self.0)(adapters)
+    }
+}
+
+impl fmt::Debug for AdapterSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("AdapterSelector")
+    }
+}
+
+/// Options for the splash screen window.
+#[derive(Debug, Clone)]
+pub struct SplashConfig {
+    /// If false, no splash screen is shown and the window is created
+    /// with the app's window attributes right away.
+    pub enabled: bool,
+    pub title: String,
+    pub always_on_top: bool,
+    pub image: SplashImage,
+    pub scaling: SplashScaling,
+    /// RGBA colour shown wherever the image does not cover the window.
+    pub background: [u8; 4],
+    /// Size of the splash window, defaults to the size of the image.
+    pub size: Option<PhysicalSize<u32>>,
+    /// The splash screen is shown for at least this long, even if startup is faster.
+    pub min_duration: Duration,
+    /// If set, the splash screen fades out over this duration before the app starts.
+    pub fade_out: Option<Duration>,
+    /// Show a progress bar and status text of the startup tasks at the bottom of the splash.
+    pub show_progress: bool,
+    /// RGBA colour of the progress bar and status text.
+    pub progress_color: [u8; 4],
+}
+
+impl Default for SplashConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            title: "Y-ENGINE".to_string(),
+            always_on_top: true,
+            image: SplashImage::Default,
+            scaling: SplashScaling::Native,
+            background: [0, 0, 0, 0],
+            size: None,
+            min_duration: Duration::ZERO,
+            fade_out: None,
+            show_progress: true,
+            progress_color: [0, 0, 0, 255],
+        }
+    }
+}
+
+/// Where the splash image comes from.
+#[derive(Debug, Clone)]
+pub enum SplashImage {
+    /// The y-engine logo.
+    Default,
+    /// Encoded image data (png, jpeg, etc.).
+    Bytes(Cow<'static, [u8]>),
+    /// Path to an image file, loaded on startup.
+    Path(PathBuf),
+    Image(RgbaImage),
+}
+
+impl SplashImage {
+    pub fn load(&self) -> Result<RgbaImage, EngineError> {
+        Ok(match self {
+            Self::Default => image::load_from_memory(DEFAULT_SPLASH_IMAGE)?.to_rgba8(),
+            Self::Bytes(bytes) => image::load_from_memory(bytes)?.to_rgba8(),
+            Self::Path(path) => image::open(path)?.to_rgba8(),
+            Self::Image(image) => image.clone(),
+        })
+    }
+}
+
+/// How the splash image is fitted into the splash window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SplashScaling {
+    /// Unscaled and centered, cropped if larger than the window.
+    #[default]
+    Native,
+    /// Scaled to fit inside the window, keeping the aspect ratio.
+    Fit,
+    /// Scaled to cover the whole window, keeping the aspect ratio (cropped).
+    Fill,
+    /// Scaled to exactly the window size, ignoring the aspect ratio.
+    Stretch,
+}
+
+/// Options for the loop that drives [`crate::app::App::update`] and
+/// [`crate::app::App::window_redraw`] once the app is running.
+#[derive(Debug, Clone)]
+pub struct LoopConfig {
+    pub mode: LoopMode,
+    /// Fixed updates per second, see [`crate::app::App::fixed_update`].
+    pub tick_rate: u32,
+    /// The most fixed updates run per frame. If a frame takes longer than that,
+    /// the simulation slows down instead of falling further and further behind.
+    /// At least 1.
+    pub max_fixed_updates: u32,
+    /// Caps the frame rate by blocking before each frame until it is due, precise enough
+    /// to pace frames with vsync off. Unlike [`LoopMode::TargetFps`], the event loop
+    /// does not handle events while waiting.
+    pub frame_limit: Option<f64>,
+    /// Number of frames [`crate::util::time::FrameStats`] are calculated over.
+    pub stats_frames: usize,
+}
+
+impl Default for LoopConfig {
+    fn default() -> Self {
+        Self {
+            mode: LoopMode::default(),
+            tick_rate: 60,
+            max_fixed_updates: 8,
+            frame_limit: None,
+            stats_frames: 120,
+        }
+    }
+}
+
+impl LoopConfig {
+    /// Time between two fixed updates.
+    pub fn fixed_step(&self) -> Duration {
+        Duration::from_secs(1) / self.tick_rate.max(1)
+    }
+}
+
+/// When the engine renders a new frame.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum LoopMode {
+    /// Render frames continuously, as fast as the present mode allows.
+    #[default]
+    Poll,
+    /// Only render when the app calls [`Window::request_redraw`] (or the os asks for it).
+    Wait,
+    /// Render frames continuously at (up to) this many frames per second,
+    /// waiting for the next frame in between.
+    TargetFps(f64),
+}
+
+/// Applies the attributes to an already existing window.
+///
+/// Attributes that can only be set on window creation (like the parent window or
+/// platform specific ones) are ignored. Sizes and positions are only applied if set.
+pub(crate) fn apply_window_attributes(window: &Window, attributes: &WindowAttributes) {
+    window.set_title(&attributes.title);
+    window.set_decorations(attributes.decorations);
+    window.set_resizable(attributes.resizable);
+    window.set_enabled_buttons(attributes.enabled_buttons);
+    window.set_window_level(attributes.window_level);
+    window.set_transparent(attributes.transparent);
+    window.set_blur(attributes.blur);
+    window.set_window_icon(attributes.window_icon.clone());
+    window.set_theme(attributes.preferred_theme);
+    window.set_content_protected(attributes.content_protected);
+    window.set_cursor(attributes.cursor.clone());
+    window.set_min_inner_size(attributes.min_inner_size);
+    window.set_max_inner_size(attributes.max_inner_size);
+    window.set_resize_increments(attributes.resize_increments);
+    if let Some(size) = attributes.inner_size {
+        let _ = window.request_inner_size(size);
+    }
+    if let Some(position) = attributes.position {
+        window.set_outer_position(position);
+    }
+    window.set_maximized(attributes.maximized);
+    window.set_fullscreen(attributes.fullscreen.clone());
+    window.set_visible(attributes.visible);
+    if attributes.active {
+        window.focus_window();
+    }
+}
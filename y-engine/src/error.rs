@@ -0,0 +1,128 @@
+use std::{error::Error, fmt};
+
+use wgpu::{rwh::HandleError, BufferAsyncError, CreateSurfaceError, Features, RequestDeviceError};
+use winit::error::{EventLoopError, OsError};
+
+use crate::startup::TaskError;
+
+/// Everything that can go wrong while the engine starts up or in the render core.
+#[derive(Debug)]
+pub enum EngineError {
+    EventLoop(EventLoopError),
+    WindowCreationFailed(OsError),
+    /// The window did not provide the handles needed to render into it.
+    WindowHandle(HandleError),
+    /// The splash screen could not be drawn (softbuffer).
+    Softbuffer(String),
+    /// The configured splash image could not be loaded.
+    SplashImage(image::ImageError),
+    SurfaceCreationFailed(CreateSurfaceError),
+    /// No adapter matching the `GpuConfig` could be found.
+    NoAdapter,
+    /// The best adapter lacks these required features.
+    MissingFeatures(Features),
+    /// The best adapter does not support these required limits.
+    MissingLimits(Vec<String>),
+    DeviceRequestFailed(RequestDeviceError),
+    /// The surface reports no supported texture formats for the chosen adapter.
+    SurfaceUnsupported,
+    /// An initialization thread panicked, holds the panic message.
+    InitThreadPanicked(String),
+    /// A startup task registered by the app returned an error.
+    StartupTaskFailed {
+        task: String,
+        error: TaskError,
+    },
+    /// Reading back the offscreen target of a headless render core failed.
+    Readback(BufferAsyncError),
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EventLoop(e) => write!(f, "event loop error: {e}"),
+            Self::WindowCreationFailed(e) => write!(f, "failed to create window: {e}"),
+            Self::WindowHandle(e) => write!(f, "failed to get window handle: {e}"),
+            Self::Softbuffer(e) => write!(f, "failed to draw splash screen: {e}"),
+            Self::SplashImage(e) => write!(f, "failed to load splash image: {e}"),
+            Self::SurfaceCreationFailed(e) => write!(f, "failed to create surface: {e}"),
+            Self::NoAdapter => write!(f, "no suitable GPU adapter found"),
+            Self::MissingFeatures(features) => {
+                write!(
+                    f,
+                    "GPU adapter does not support required features: {features:?}"
+                )
+            }
+            Self::MissingLimits(limits) => write!(
+                f,
+                "GPU adapter does not support required limits: {}",
+                limits.join(", ")
+            ),
+            Self::DeviceRequestFailed(e) => write!(f, "failed to request GPU device: {e}"),
+            Self::SurfaceUnsupported => write!(f, "surface is not supported by the GPU adapter"),
+            Self::InitThreadPanicked(msg) => write!(f, "initialization thread panicked: {msg}"),
+            Self::StartupTaskFailed { task, error } => {
+                write!(f, "startup task \"{task}\" failed: {error}")
+            }
+            Self::Readback(e) => write!(f, "failed to read back offscreen target: {e}"),
+        }
+    }
+}
+
+impl Error for EngineError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::EventLoop(e) => Some(e),
+            Self::WindowCreationFailed(e) => Some(e),
+            Self::WindowHandle(e) => Some(e),
+            Self::SplashImage(e) => Some(e),
+            Self::SurfaceCreationFailed(e) => Some(e),
+            Self::DeviceRequestFailed(e) => Some(e),
+            Self::StartupTaskFailed { error, .. } => Some(error.as_ref()),
+            Self::Readback(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<EventLoopError> for EngineError {
+    fn from(e: EventLoopError) -> Self {
+        Self::EventLoop(e)
+    }
+}
+
+impl From<OsError> for EngineError {
+    fn from(e: OsError) -> Self {
+        Self::WindowCreationFailed(e)
+    }
+}
+
+impl From<HandleError> for EngineError {
+    fn from(e: HandleError) -> Self {
+        Self::WindowHandle(e)
+    }
+}
+
+impl From<softbuffer::SoftBufferError> for EngineError {
+    fn from(e: softbuffer::SoftBufferError) -> Self {
+        Self::Softbuffer(e.to_string())
+    }
+}
+
+impl From<image::ImageError> for EngineError {
+    fn from(e: image::ImageError) -> Self {
+        Self::SplashImage(e)
+    }
+}
+
+impl From<CreateSurfaceError> for EngineError {
+    fn from(e: CreateSurfaceError) -> Self {
+        Self::SurfaceCreationFailed(e)
+    }
+}
+
+impl From<RequestDeviceError> for EngineError {
+    fn from(e: RequestDeviceError) -> Self {
+        Self::DeviceRequestFailed(e)
+    }
+}
@@ -0,0 +1,46 @@
+use winit::event_loop::{EventLoopClosed, EventLoopProxy};
+
+/// Events sent through the engine's event loop.
+/// Apps send their own events with an [`EventProxy`], which wraps them in `User`.
+#[derive(Debug)]
+pub enum EngineEvent<E> {
+    /// Only wakes up the event loop (e.g. when a startup task made progress).
+    Wake,
+    User(E),
+}
+
+/// Sends events to [`crate::app::App::user_event`] from any thread, waking up the event loop.
+///
+/// A wrapper around winit's [`EventLoopProxy`], handed to [`crate::app::App::new`].
+pub struct EventProxy<E: 'static> {
+    proxy: EventLoopProxy<EngineEvent<E>>,
+}
+
+impl<E: 'static> EventProxy<E> {
+    pub(crate) fn new(proxy: EventLoopProxy<EngineEvent<E>>) -> Self {
+        Self { proxy }
+    }
+
+    /// Returns the event back if the event loop has already exited.
+    pub fn send_event(&self, event: E) -> Result<(), EventLoopClosed<E>> {
+        self.proxy.send_event(EngineEvent::User(event)).map_err(
+            |EventLoopClosed(event)| match event {
+                EngineEvent::User(event) => EventLoopClosed(event),
+                EngineEvent::Wake => unreachable!("Sent a user event"),
+            },
+        )
+    }
+
+    /// Wakes up the event loop without an event.
+    pub(crate) fn wake(&self) {
+        let _ = self.proxy.send_event(EngineEvent::Wake);
+    }
+}
+
+impl<E: 'static> Clone for EventProxy<E> {
+    fn clone(&self) -> Self {
+        Self {
+            proxy: self.proxy.clone(),
+        }
+    }
+}
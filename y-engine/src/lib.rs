@@ -1,30 +1,114 @@
+use std::sync::Arc;
+
 use app::App;
+use config::{EngineConfig, GpuConfig, LoopConfig, SplashConfig};
+use error::EngineError;
+use event::{EngineEvent, EventProxy};
+use startup::StartupTasks;
 use state::{running, State};
 use winit::{
     application::ApplicationHandler,
-    event::WindowEvent,
-    event_loop::{ActiveEventLoop, EventLoop},
-    window::WindowId,
+    event::{DeviceEvent, DeviceId, StartCause, WindowEvent},
+    event_loop::{ActiveEventLoop, EventLoop, EventLoopProxy},
+    window::{WindowAttributes, WindowId},
 };
 
 pub mod app;
+pub mod config;
+pub mod error;
+pub mod event;
+pub mod startup;
 pub mod state;
 pub mod util;
 
 pub struct YEngine<T: App + 'static> {
     inner: Option<YEngineInner<T>>,
+    config: EngineConfig,
+    error: Option<EngineError>,
+    /// Set once the event loop is created.
+    proxy: Option<EventLoopProxy<EngineEvent<T::UserEvent>>>,
 }
 
-impl<T: App> ApplicationHandler for YEngine<T> {
+impl<T: App> ApplicationHandler<EngineEvent<T::UserEvent>> for YEngine<T> {
+    fn new_events(&mut self, event_loop: &ActiveEventLoop, cause: StartCause) {
+        if let Some(app) = self.app_mut() {
+            app.new_events(cause, event_loop);
+        }
+    }
+
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         if self.inner.is_none() {
-            self.inner = Some(YEngineInner::new(event_loop));
+            if self.error.is_some() {
+                return;
+            }
+            let config = std::mem::take(&mut self.config);
+            let proxy = EventProxy::new(self.proxy.clone().expect("Event loop is running"));
+            match YEngineInner::new(event_loop, config, proxy) {
+                Ok(inner) => self.inner = Some(inner),
+                Err(error) => self.fail(event_loop, error),
+            }
+        } else if let Some(app) = self.app_mut() {
+            app.resumed(event_loop);
+        }
+    }
+
+    fn suspended(&mut self, event_loop: &ActiveEventLoop) {
+        if let Some(app) = self.app_mut() {
+            app.suspended(event_loop);
+        }
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        if let Some(inner) = self.inner.as_mut() {
+            if let Err(error) = inner.handle_window_event(event_loop, window_id, event) {
+                self.fail(event_loop, error);
+            }
+        }
+    }
+
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, event: EngineEvent<T::UserEvent>) {
+        // Wake events only make the event loop run about_to_wait again.
+        if let EngineEvent::User(event) = event {
+            if let Some(app) = self.app_mut() {
+                app.user_event(event, event_loop);
+            }
         }
     }
 
-    fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent) {
+    fn device_event(&mut self, _: &ActiveEventLoop, device_id: DeviceId, event: DeviceEvent) {
         if let Some(inner) = self.inner.as_mut() {
-            inner.handle_window_event(event_loop, event);
+            inner.state.handle_device_event(device_id, event);
+        }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        if let Some(app) = self.app_mut() {
+            app.about_to_wait(event_loop);
+        }
+        if let Some(inner) = self.inner.as_mut() {
+            if let Err(error) = inner.about_to_wait(event_loop) {
+                self.fail(event_loop, error);
+            }
+        }
+    }
+
+    fn exiting(&mut self, event_loop: &ActiveEventLoop) {
+        if let Some(app) = self.app_mut() {
+            app.exiting(event_loop);
+        }
+        if let Some(mut inner) = self.inner.take() {
+            inner.state.shutdown();
+        }
+    }
+
+    fn memory_warning(&mut self, event_loop: &ActiveEventLoop) {
+        if let Some(app) = self.app_mut() {
+            app.memory_warning(event_loop);
         }
     }
 }
@@ -37,43 +121,145 @@ impl<T: App + 'static> Default for YEngine<T> {
 
 impl<T: App + 'static> YEngine<T> {
     pub fn new() -> Self {
-        Self { inner: None }
+        Self::with_config(EngineConfig::default())
+    }
+
+    pub fn with_config(config: EngineConfig) -> Self {
+        Self {
+            inner: None,
+            config,
+            error: None,
+            proxy: None,
+        }
     }
 
-    pub fn run(&mut self) {
-        let event_loop = EventLoop::new().unwrap();
-        event_loop.run_app(self).unwrap();
+    pub fn builder() -> YEngineBuilder<T> {
+        YEngineBuilder {
+            config: EngineConfig::default(),
+            phantom: std::marker::PhantomData,
+        }
+    }
+
+    /// Runs the engine until the event loop exits.
+    ///
+    /// Returns an error if the engine failed to start up, [`App::startup_failed`]
+    /// has already been called with it at that point.
+    pub fn run(&mut self) -> Result<(), EngineError> {
+        let event_loop = match EventLoop::with_user_event().build() {
+            Ok(event_loop) => event_loop,
+            Err(error) => {
+                let error = EngineError::from(error);
+                T::startup_failed(&error);
+                return Err(error);
+            }
+        };
+        self.proxy = Some(event_loop.create_proxy());
+        event_loop.run_app(self)?;
+        match self.error.take() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// The running app, if there is one.
+    fn app_mut(&mut self) -> Option<&mut dyn App<UserEvent = T::UserEvent>> {
+        self.inner.as_mut()?.state.app_mut()
+    }
+
+    fn fail(&mut self, event_loop: &ActiveEventLoop, error: EngineError) {
+        T::startup_failed(&error);
+        // Drop the splash window and init threads' results before exiting.
+        if let Some(mut inner) = self.inner.take() {
+            inner.state.shutdown();
+        }
+        self.error = Some(error);
+        event_loop.exit();
     }
 }
 
-struct YEngineInner<T: App + 'static> {
-    state: state::State,
+pub struct YEngineBuilder<T: App + 'static> {
+    config: EngineConfig,
     phantom: std::marker::PhantomData<T>,
 }
 
+impl<T: App + 'static> YEngineBuilder<T> {
+    /// Attributes of the window the app runs in (after the splash screen).
+    pub fn window(mut self, attributes: WindowAttributes) -> Self {
+        self.config.window = attributes;
+        self
+    }
+
+    pub fn gpu(mut self, gpu: GpuConfig) -> Self {
+        self.config.gpu = gpu;
+        self
+    }
+
+    pub fn splash(mut self, splash: SplashConfig) -> Self {
+        self.config.splash = splash;
+        self
+    }
+
+    pub fn game_loop(mut self, game_loop: LoopConfig) -> Self {
+        self.config.game_loop = game_loop;
+        self
+    }
+
+    pub fn build(self) -> YEngine<T> {
+        YEngine::with_config(self.config)
+    }
+}
+
+struct YEngineInner<T: App + 'static> {
+    state: state::State<T::UserEvent>,
+    proxy: EventProxy<T::UserEvent>,
+}
+
 impl<T: App> YEngineInner<T> {
-    fn new(event_loop: &ActiveEventLoop) -> Self {
-        Self {
-            state: state::State::new(event_loop),
-            phantom: std::marker::PhantomData,
-        }
+    fn new(
+        event_loop: &ActiveEventLoop,
+        config: EngineConfig,
+        proxy: EventProxy<T::UserEvent>,
+    ) -> Result<Self, EngineError> {
+        let mut tasks = StartupTasks::default();
+        T::startup_tasks(&mut tasks);
+        let waker = proxy.clone();
+        Ok(Self {
+            state: state::State::new(event_loop, config, tasks, Arc::new(move || waker.wake()))?,
+            proxy,
+        })
     }
 
-    fn transition_to_running(&mut self) {
+    fn transition_to_running(&mut self, event_loop: &ActiveEventLoop) {
         let old_state = std::mem::replace(&mut self.state, state::State::None);
         let State::Starting(starting) = old_state else {
             panic!("Expected starting state");
         };
-        self.state = State::Running(running::State::new::<T>(starting));
+        let mut running = running::State::new::<T>(*starting, self.proxy.clone());
+        running.window().request_redraw();
+        running.about_to_wait(event_loop);
+        self.state = State::Running(Box::new(running));
+    }
+
+    fn handle_window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_id: WindowId,
+        event: WindowEvent,
+    ) -> Result<(), EngineError> {
+        self.state.handle_window_event(event_loop, window_id, event)
     }
 
-    fn handle_window_event(&mut self, event_loop: &ActiveEventLoop, event: WindowEvent) {
-        if let State::Starting(state) = &self.state {
-            if state.finished() {
-                self.transition_to_running();
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) -> Result<(), EngineError> {
+        match &mut self.state {
+            State::Starting(state) => {
+                state.update(event_loop)?;
+                if state.finished() {
+                    self.transition_to_running(event_loop);
+                }
             }
+            State::Running(state) => state.about_to_wait(event_loop),
+            State::None => {}
         }
-
-        self.state.handle_window_event(event_loop, event);
+        Ok(())
     }
 }
@@ -0,0 +1,125 @@
+use std::{
+    any::{Any, TypeId},
+    error::Error,
+};
+
+use crossbeam::channel::Sender;
+use rustc_hash::FxHashMap;
+
+use crate::state::{StateMsg, Waker};
+
+pub type TaskError = Box<dyn Error + Send + Sync>;
+
+type TaskFn = Box<dyn FnOnce(&TaskProgress) -> Result<Box<dyn Any + Send>, TaskError> + Send>;
+
+pub(crate) struct StartupTask {
+    pub name: String,
+    pub run: TaskFn,
+}
+
+/// Collects the tasks an app wants to run on background threads while the splash screen is shown.
+///
+/// Each task produces a result of a distinct type, which is handed to [`crate::app::App::new`]
+/// through [`StartupResults`].
+///
+/// ```rust,no_run
+/// # use y_engine::startup::StartupTasks;
+/// # struct Config;
+/// # fn load_config() -> std::io::Result<Config> { Ok(Config) }
+/// # let mut tasks = StartupTasks::default();
+/// tasks.add("Loading config", |progress| {
+///     progress.set_status("Reading config.toml");
+///     let config = load_config()?;
+///     progress.set_progress(1.0);
+///     Ok(config)
+/// });
+/// ```
+#[derive(Default)]
+pub struct StartupTasks {
+    tasks: Vec<StartupTask>,
+    result_types: Vec<TypeId>,
+}
+
+impl StartupTasks {
+    /// Panics if a task with the same result type was already added.
+    pub fn add<R, F>(&mut self, name: impl Into<String>, task: F)
+    where
+        R: Send + 'static,
+        F: FnOnce(&TaskProgress) -> Result<R, TaskError> + Send + 'static,
+    {
+        let type_id = TypeId::of::<R>();
+        if self.result_types.contains(&type_id) {
+            panic!("Startup task result type collision.");
+        }
+        self.result_types.push(type_id);
+        self.tasks.push(StartupTask {
+            name: name.into(),
+            run: Box::new(move |progress| {
+                task(progress).map(|result| Box::new(result) as Box<dyn Any + Send>)
+            }),
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    pub(crate) fn into_tasks(self) -> Vec<StartupTask> {
+        self.tasks
+    }
+}
+
+/// Handed to a running startup task to report its progress to the splash screen.
+pub struct TaskProgress {
+    pub(crate) task: usize,
+    pub(crate) msg_tx: Sender<StateMsg>,
+    pub(crate) waker: Waker,
+}
+
+impl TaskProgress {
+    /// Sets the progress of this task, from 0.0 to 1.0.
+    pub fn set_progress(&self, progress: f32) {
+        let _ = self.msg_tx.send(StateMsg::TaskProgress {
+            task: self.task,
+            progress: progress.clamp(0.0, 1.0),
+        });
+        (self.waker)();
+    }
+
+    /// Sets the status text shown below the splash screen's progress bar.
+    pub fn set_status(&self, status: impl Into<String>) {
+        let _ = self.msg_tx.send(StateMsg::TaskStatus {
+            task: self.task,
+            status: status.into(),
+        });
+        (self.waker)();
+    }
+}
+
+/// The results of all startup tasks, keyed by their type.
+#[derive(Default)]
+pub struct StartupResults {
+    results: FxHashMap<TypeId, Box<dyn Any + Send>>,
+}
+
+impl StartupResults {
+    pub(crate) fn insert(&mut self, result: Box<dyn Any + Send>) {
+        self.results.insert(Any::type_id(&*result), result);
+    }
+
+    /// Panics if no task produced a result of this type or it was already taken.
+    pub fn take<R: 'static>(&mut self) -> R {
+        self.try_take()
+            .expect("Startup result not found, was the task added?")
+    }
+
+    /// Does not panic.
+    pub fn try_take<R: 'static>(&mut self) -> Option<R> {
+        let result = self.results.remove(&TypeId::of::<R>())?;
+        Some(*result.downcast::<R>().expect("Type id matches"))
+    }
+}
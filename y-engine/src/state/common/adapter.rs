@@ -0,0 +1,142 @@
+use wgpu::{Adapter, DeviceType, Features, Instance, Limits, PowerPreference, Surface};
+
+use crate::{config::GpuConfig, error::EngineError};
+
+/// Picks an adapter according to the policy described on [`GpuConfig`].
+pub(crate) fn select_adapter(
+    instance: &Instance,
+    config: &GpuConfig,
+    surface: Option<&Surface>,
+) -> Result<Adapter, EngineError> {
+    let (mut candidates, mut unsupported): (Vec<Adapter>, Vec<Adapter>) = instance
+        .enumerate_adapters(config.backends)
+        .into_iter()
+        .filter(|adapter| is_usable(adapter, config, surface))
+        .partition(|adapter| supports_requirements(adapter, config));
+    if candidates.is_empty() {
+        // Report what the best adapter is missing, so the error says more than "no adapter".
+        unsupported
+            .sort_by_key(|adapter| rank(adapter.get_info().device_type, config.power_preference));
+        let Some(best) = unsupported.first() else {
+            return Err(EngineError::NoAdapter);
+        };
+        let missing_features = config.required_features - best.features();
+        if !missing_features.is_empty() {
+            return Err(EngineError::MissingFeatures(missing_features));
+        }
+        let mut missing_limits = Vec::new();
+        config.required_limits.check_limits_with_fail_fn(
+            &best.limits(),
+            false,
+            |name, required, supported| {
+                missing_limits.push(format!(
+                    "{name} (required {required}, supported {supported})"
+                ));
+            },
+        );
+        return Err(EngineError::MissingLimits(missing_limits));
+    }
+
+    if let Some(selector) = &config.adapter_selector {
+        let infos: Vec<_> = candidates.iter().map(Adapter::get_info).collect();
+        if let Some(index) = selector.select(&infos) {
+            if index < candidates.len() {
+                return Ok(candidates.swap_remove(index));
+            }
+        }
+    }
+
+    // Stable sort, so adapters of the same type keep the order of the backends.
+    candidates.sort_by_key(|adapter| rank(adapter.get_info().device_type, config.power_preference));
+    Ok(candidates.swap_remove(0))
+}
+
+/// Features to request from the device: the required ones plus the supported optional ones.
+pub(crate) fn device_features(adapter: &Adapter, config: &GpuConfig) -> Features {
+    config.required_features | (config.optional_features & adapter.features())
+}
+
+/// Limits to request from the device: the optional limits as far as the adapter supports them,
+/// but never worse than the required limits.
+pub(crate) fn device_limits(adapter: &Adapter, config: &GpuConfig) -> Limits {
+    let supported = adapter.limits();
+    let (optional, required) = (&config.optional_limits, &config.required_limits);
+    let mut limits = required.clone();
+    macro_rules! better_is_higher {
+        ($($name:ident),* $(,)?) => {
+            $(limits.$name = optional.$name.min(supported.$name).max(required.$name);)*
+        };
+    }
+    macro_rules! better_is_lower {
+        ($($name:ident),* $(,)?) => {
+            $(limits.$name = optional.$name.max(supported.$name).min(required.$name);)*
+        };
+    }
+    better_is_higher!(
+        max_texture_dimension_1d,
+        max_texture_dimension_2d,
+        max_texture_dimension_3d,
+        max_texture_array_layers,
+        max_bind_groups,
+        max_bindings_per_bind_group,
+        max_dynamic_uniform_buffers_per_pipeline_layout,
+        max_dynamic_storage_buffers_per_pipeline_layout,
+        max_sampled_textures_per_shader_stage,
+        max_samplers_per_shader_stage,
+        max_storage_buffers_per_shader_stage,
+        max_storage_textures_per_shader_stage,
+        max_uniform_buffers_per_shader_stage,
+        max_uniform_buffer_binding_size,
+        max_storage_buffer_binding_size,
+        max_vertex_buffers,
+        max_buffer_size,
+        max_vertex_attributes,
+        max_vertex_buffer_array_stride,
+        max_inter_stage_shader_components,
+        max_color_attachments,
+        max_color_attachment_bytes_per_sample,
+        max_compute_workgroup_storage_size,
+        max_compute_invocations_per_workgroup,
+        max_compute_workgroup_size_x,
+        max_compute_workgroup_size_y,
+        max_compute_workgroup_size_z,
+        max_compute_workgroups_per_dimension,
+        max_push_constant_size,
+        max_non_sampler_bindings,
+    );
+    better_is_lower!(
+        min_uniform_buffer_offset_alignment,
+        min_storage_buffer_offset_alignment,
+    );
+    limits
+}
+
+/// Checks everything except the required features and limits.
+fn is_usable(adapter: &Adapter, config: &GpuConfig, surface: Option<&Surface>) -> bool {
+    let is_software = adapter.get_info().device_type == DeviceType::Cpu;
+    if config.force_fallback_adapter && !is_software {
+        return false;
+    }
+    if !config.allow_fallback_adapter && !config.force_fallback_adapter && is_software {
+        return false;
+    }
+    surface.is_none_or(|surface| adapter.is_surface_supported(surface))
+}
+
+fn supports_requirements(adapter: &Adapter, config: &GpuConfig) -> bool {
+    adapter.features().contains(config.required_features)
+        && config.required_limits.check_limits(&adapter.limits())
+}
+
+/// Lower is better. Software adapters always come last.
+fn rank(device_type: DeviceType, power_preference: PowerPreference) -> u8 {
+    match (device_type, power_preference) {
+        (DeviceType::DiscreteGpu, PowerPreference::LowPower) => 1,
+        (DeviceType::DiscreteGpu, _) => 0,
+        (DeviceType::IntegratedGpu, PowerPreference::LowPower) => 0,
+        (DeviceType::IntegratedGpu, _) => 1,
+        (DeviceType::VirtualGpu, _) => 2,
+        (DeviceType::Other, _) => 3,
+        (DeviceType::Cpu, _) => 4,
+    }
+}
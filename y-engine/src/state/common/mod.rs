@@ -1 +1,3 @@
+mod adapter;
 pub mod render;
+pub mod surface;
@@ -1,11 +1,308 @@
-use wgpu::{Adapter, Device, Instance, Queue, Surface, SurfaceConfiguration};
+use std::sync::Arc;
+
+use image::RgbaImage;
+use wgpu::*;
+use winit::{dpi::PhysicalSize, window::Window};
+
+use crate::{config::GpuConfig, error::EngineError};
+
+use super::{
+    adapter::{device_features, device_limits, select_adapter},
+    surface::WindowSurface,
+};
+
+/// Format of the offscreen target of headless render cores.
+const OFFSCREEN_FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
 
 /// Holds the core render objects like the device, queue, and surface.
 pub struct RenderCore {
     pub instance: Instance,
     pub adapter: Adapter,
+    /// Info about the chosen adapter, e.g. for crash reports.
+    pub adapter_info: AdapterInfo,
+    /// Features enabled on the device: the required ones plus the supported optional ones.
+    pub features: Features,
+    /// Limits of the device, see [`GpuConfig::optional_limits`].
+    pub limits: Limits,
     pub device: Device,
     pub queue: Queue,
-    pub surface: Surface<'static>,
-    pub surface_config: SurfaceConfiguration,
+    /// None for headless render cores. Shared with the engine, which resizes it with the window.
+    pub surface: Option<Arc<WindowSurface>>,
+    /// Only set for headless render cores.
+    pub offscreen: Option<OffscreenTarget>,
+    /// Used to configure the surfaces of additional windows.
+    config: GpuConfig,
+}
+
+/// A texture that is rendered into instead of a window surface.
+pub struct OffscreenTarget {
+    pub texture: Texture,
+    pub view: TextureView,
+}
+
+impl RenderCore {
+    /// Creates a render core that renders into the given window.
+    pub fn new(window: Arc<Window>, config: &GpuConfig) -> Result<Self, EngineError> {
+        let instance = create_instance(config);
+
+        let surface =
+            unsafe { instance.create_surface_unsafe(SurfaceTargetUnsafe::from_window(&window)?)? };
+
+        let adapter = select_adapter(&instance, config, Some(&surface))?;
+        let (device, queue) = request_device(&adapter, config)?;
+        let surface = WindowSurface::new(surface, &adapter, &device, window.inner_size(), config)?;
+
+        Ok(Self {
+            instance,
+            adapter_info: adapter.get_info(),
+            features: device.features(),
+            limits: device.limits(),
+            adapter,
+            device,
+            queue,
+            surface: Some(Arc::new(surface)),
+            offscreen: None,
+            config: config.clone(),
+        })
+    }
+
+    /// Creates a render core without a window, rendering into an offscreen texture
+    /// that can be read back with [`RenderCore::read_offscreen`].
+    ///
+    /// Falls back to wgpu's software adapter if no other adapter is found (unless disabled
+    /// in the config), so this also works on machines without a display or gpu.
+    ///
+    /// ```rust,no_run
+    /// # use y_engine::{config::GpuConfig, state::common::render::RenderCore};
+    /// let render_core = RenderCore::new_headless(&GpuConfig::default(), 256, 256).unwrap();
+    /// let view = &render_core.offscreen.as_ref().unwrap().view;
+    /// // ... render into `view` ...
+    /// let image = render_core.read_offscreen().unwrap();
+    /// ```
+    pub fn new_headless(config: &GpuConfig, width: u32, height: u32) -> Result<Self, EngineError> {
+        let instance = create_instance(config);
+
+        let adapter = select_adapter(&instance, config, None)?;
+        let (device, queue) = request_device(&adapter, config)?;
+        let offscreen = OffscreenTarget::new(&device, width, height);
+
+        Ok(Self {
+            instance,
+            adapter_info: adapter.get_info(),
+            features: device.features(),
+            limits: device.limits(),
+            adapter,
+            device,
+            queue,
+            surface: None,
+            offscreen: Some(offscreen),
+            config: config.clone(),
+        })
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.surface.is_none()
+    }
+
+    /// Format of the window surface or the offscreen target.
+    pub fn surface_format(&self) -> TextureFormat {
+        match &self.surface {
+            Some(surface) => surface.format(),
+            None => OFFSCREEN_FORMAT,
+        }
+    }
+
+    /// Size of the window surface or the offscreen target.
+    pub fn surface_size(&self) -> PhysicalSize<u32> {
+        match (&self.surface, &self.offscreen) {
+            (Some(surface), _) => surface.size(),
+            (None, Some(offscreen)) => {
+                let size = offscreen.texture.size();
+                PhysicalSize::new(size.width, size.height)
+            }
+            (None, None) => {
+                unreachable!("Render core has neither a surface nor an offscreen target")
+            }
+        }
+    }
+
+    /// Resizes the window surface or recreates the offscreen target with the new size.
+    /// Zero sizes (minimized windows) are ignored.
+    ///
+    /// The engine already does this for the main window before [`crate::app::App::window_resized`]
+    /// is called.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        if let Some(surface) = &self.surface {
+            surface.resize(width, height);
+        } else if self.surface_size() != PhysicalSize::new(width, height) {
+            self.offscreen = Some(OffscreenTarget::new(&self.device, width, height));
+        }
+    }
+
+    /// Gets the next texture of the window surface to render into.
+    /// Lost or outdated surfaces are reconfigured and acquiring is retried.
+    ///
+    /// Panics if the render core is headless.
+    pub fn acquire_frame(&self) -> Result<SurfaceTexture, SurfaceError> {
+        self.surface
+            .as_ref()
+            .expect("Headless render cores have no surface, render into the offscreen target.")
+            .acquire_frame()
+    }
+
+    /// Creates a surface for an additional window, sharing this render core's device and queue.
+    ///
+    /// The app owns the window and its surface. Its events are passed to
+    /// [`crate::app::App::secondary_window_event`], the surface has to be resized there.
+    ///
+    /// ```rust,no_run
+    /// # use std::sync::Arc;
+    /// # use winit::{event_loop::ActiveEventLoop, window::WindowAttributes};
+    /// # use y_engine::state::common::render::RenderCore;
+    /// # fn f(event_loop: &ActiveEventLoop, render_core: &RenderCore) {
+    /// let window = Arc::new(
+    ///     event_loop
+    ///         .create_window(WindowAttributes::default().with_title("Inspector"))
+    ///         .unwrap(),
+    /// );
+    /// let surface = render_core.create_window_surface(window.clone()).unwrap();
+    /// # }
+    /// ```
+    pub fn create_window_surface(
+        &self,
+        window: Arc<Window>,
+    ) -> Result<Arc<WindowSurface>, EngineError> {
+        let size = window.inner_size();
+        let surface = self.instance.create_surface(window)?;
+        if !self.adapter.is_surface_supported(&surface) {
+            return Err(EngineError::SurfaceUnsupported);
+        }
+        let surface = WindowSurface::new(surface, &self.adapter, &self.device, size, &self.config)?;
+        Ok(Arc::new(surface))
+    }
+
+    /// Switches the present mode (vsync) of the window surface at runtime, falling back to the
+    /// closest supported mode. See [`WindowSurface::set_present_mode`].
+    ///
+    /// Returns the effective present mode, or None for headless render cores.
+    pub fn set_present_mode(&self, present_mode: PresentMode) -> Option<PresentMode> {
+        Some(self.surface.as_ref()?.set_present_mode(present_mode))
+    }
+
+    /// The effective present mode of the window surface, None for headless render cores.
+    pub fn present_mode(&self) -> Option<PresentMode> {
+        Some(self.surface.as_ref()?.present_mode())
+    }
+
+    /// Copies the offscreen target back to the cpu, blocking until the gpu is done.
+    ///
+    /// Panics if the render core is not headless.
+    pub fn read_offscreen(&self) -> Result<RgbaImage, EngineError> {
+        let offscreen = self
+            .offscreen
+            .as_ref()
+            .expect("Only headless render cores have an offscreen target.");
+        let PhysicalSize { width, height } = self.surface_size();
+        let unpadded_bytes_per_row = width * 4;
+        let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(COPY_BYTES_PER_ROW_ALIGNMENT)
+            * COPY_BYTES_PER_ROW_ALIGNMENT;
+
+        let buffer = self.device.create_buffer(&BufferDescriptor {
+            label: Some("Y-ENGINE Offscreen Readback Buffer"),
+            size: u64::from(padded_bytes_per_row * height),
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("Y-ENGINE Offscreen Readback Encoder"),
+            });
+        encoder.copy_texture_to_buffer(
+            TexelCopyTextureInfo {
+                texture: &offscreen.texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let (tx, rx) = crossbeam::channel::bounded(1);
+        let slice = buffer.slice(..);
+        slice.map_async(MapMode::Read, move |result| {
+            let _ = tx.send(result);
+        });
+        self.device.poll(Maintain::Wait);
+        rx.recv()
+            .expect("Map callback is called on poll")
+            .map_err(EngineError::Readback)?;
+
+        let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+        for row in slice
+            .get_mapped_range()
+            .chunks(padded_bytes_per_row as usize)
+        {
+            pixels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+        }
+        buffer.unmap();
+        Ok(RgbaImage::from_raw(width, height, pixels).expect("Buffer has the image size"))
+    }
+}
+
+impl OffscreenTarget {
+    fn new(device: &Device, width: u32, height: u32) -> Self {
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("Y-ENGINE Offscreen Target"),
+            size: Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: OFFSCREEN_FORMAT,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&TextureViewDescriptor::default());
+        Self { texture, view }
+    }
+}
+
+fn create_instance(config: &GpuConfig) -> Instance {
+    Instance::new(&InstanceDescriptor {
+        backends: config.backends,
+        flags: config.instance_flags,
+        backend_options: BackendOptions::default(),
+    })
+}
+
+fn request_device(adapter: &Adapter, config: &GpuConfig) -> Result<(Device, Queue), EngineError> {
+    Ok(pollster::block_on(adapter.request_device(
+        &DeviceDescriptor {
+            required_features: device_features(adapter, config),
+            required_limits: device_limits(adapter, config),
+            label: Some("Y-ENGINE GPU Device"),
+            memory_hints: config.memory_hints.clone(),
+        },
+        None,
+    ))?)
 }
@@ -0,0 +1,181 @@
+use std::sync::{Mutex, MutexGuard};
+
+use wgpu::*;
+use winit::dpi::PhysicalSize;
+
+use crate::{config::GpuConfig, error::EngineError};
+
+/// A configured window surface.
+///
+/// It is shared (via `Arc`) between the app's [`super::render::RenderCore`] and the engine,
+/// so the engine can keep it in sync with the window (e.g. resize it before
+/// [`crate::app::App::window_resized`] is called).
+pub struct WindowSurface {
+    surface: Surface<'static>,
+    config: Mutex<SurfaceConfiguration>,
+    adapter: Adapter,
+    device: Device,
+}
+
+impl WindowSurface {
+    /// Picks a format (preferring srgb) and configures the surface.
+    pub(crate) fn new(
+        surface: Surface<'static>,
+        adapter: &Adapter,
+        device: &Device,
+        size: PhysicalSize<u32>,
+        gpu_config: &GpuConfig,
+    ) -> Result<Self, EngineError> {
+        let surface_caps = surface.get_capabilities(adapter);
+        let surface_format = surface_caps
+            .formats
+            .iter()
+            .find(|f| f.is_srgb())
+            .or(surface_caps.formats.first())
+            .copied()
+            .ok_or(EngineError::SurfaceUnsupported)?;
+        let config = SurfaceConfiguration {
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            format: surface_format,
+            width: size.width.max(1),
+            height: size.height.max(1),
+            present_mode: resolve_present_mode(
+                gpu_config.present_mode,
+                &surface_caps.present_modes,
+            ),
+            alpha_mode: gpu_config.alpha_mode,
+            view_formats: vec![],
+            desired_maximum_frame_latency: gpu_config.desired_maximum_frame_latency,
+        };
+        surface.configure(device, &config);
+        Ok(Self {
+            surface,
+            config: Mutex::new(config),
+            adapter: adapter.clone(),
+            device: device.clone(),
+        })
+    }
+
+    pub fn surface(&self) -> &Surface<'static> {
+        &self.surface
+    }
+
+    /// A copy of the current configuration.
+    pub fn config(&self) -> SurfaceConfiguration {
+        self.lock_config().clone()
+    }
+
+    pub fn format(&self) -> TextureFormat {
+        self.lock_config().format
+    }
+
+    pub fn size(&self) -> PhysicalSize<u32> {
+        let config = self.lock_config();
+        PhysicalSize::new(config.width, config.height)
+    }
+
+    /// Reconfigures the surface with the new size. Zero sizes (minimized windows) are ignored.
+    ///
+    /// Returns true if the surface was reconfigured.
+    pub fn resize(&self, width: u32, height: u32) -> bool {
+        if width == 0 || height == 0 {
+            return false;
+        }
+        let mut config = self.lock_config();
+        if config.width == width && config.height == height {
+            return false;
+        }
+        config.width = width;
+        config.height = height;
+        self.surface.configure(&self.device, &config);
+        true
+    }
+
+    /// Configures the surface again with the current configuration.
+    pub fn reconfigure(&self) {
+        self.surface.configure(&self.device, &self.lock_config());
+    }
+
+    /// Gets the next texture to render into.
+    ///
+    /// If the surface is lost or outdated, it is reconfigured and acquiring is retried once.
+    pub fn acquire_frame(&self) -> Result<SurfaceTexture, SurfaceError> {
+        match self.surface.get_current_texture() {
+            Err(SurfaceError::Lost | SurfaceError::Outdated) => {
+                self.reconfigure();
+                self.surface.get_current_texture()
+            }
+            result => result,
+        }
+    }
+
+    pub fn capabilities(&self) -> SurfaceCapabilities {
+        self.surface.get_capabilities(&self.adapter)
+    }
+
+    /// The present mode the surface is configured with, never one of the `Auto*` modes.
+    pub fn present_mode(&self) -> PresentMode {
+        self.lock_config().present_mode
+    }
+
+    pub fn supported_present_modes(&self) -> Vec<PresentMode> {
+        self.capabilities().present_modes
+    }
+
+    /// Reconfigures the surface with the requested present mode. If it is not supported,
+    /// the closest supported mode is used instead (e.g. `Mailbox` falls back to `Immediate`,
+    /// then `Fifo`, which is always supported).
+    ///
+    /// Returns the effective present mode.
+    pub fn set_present_mode(&self, present_mode: PresentMode) -> PresentMode {
+        let present_mode = resolve_present_mode(present_mode, &self.supported_present_modes());
+        let mut config = self.lock_config();
+        if config.present_mode != present_mode {
+            config.present_mode = present_mode;
+            self.surface.configure(&self.device, &config);
+        }
+        present_mode
+    }
+
+    /// Shorthand for `set_present_mode` with `AutoVsync` or `AutoNoVsync`.
+    pub fn set_vsync(&self, vsync: bool) -> PresentMode {
+        self.set_present_mode(if vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        })
+    }
+
+    fn lock_config(&self) -> MutexGuard<'_, SurfaceConfiguration> {
+        self.config.lock().expect("Surface config lock poisoned")
+    }
+}
+
+/// Picks the first supported mode of the fallback chain of the requested mode.
+fn resolve_present_mode(requested: PresentMode, supported: &[PresentMode]) -> PresentMode {
+    let chain: &[PresentMode] = match requested {
+        PresentMode::AutoVsync => &[PresentMode::FifoRelaxed, PresentMode::Fifo],
+        PresentMode::AutoNoVsync => &[
+            PresentMode::Immediate,
+            PresentMode::Mailbox,
+            PresentMode::Fifo,
+        ],
+        PresentMode::Mailbox => &[
+            PresentMode::Mailbox,
+            PresentMode::Immediate,
+            PresentMode::Fifo,
+        ],
+        PresentMode::Immediate => &[
+            PresentMode::Immediate,
+            PresentMode::Mailbox,
+            PresentMode::Fifo,
+        ],
+        PresentMode::FifoRelaxed => &[PresentMode::FifoRelaxed, PresentMode::Fifo],
+        PresentMode::Fifo => &[PresentMode::Fifo],
+    };
+    chain
+        .iter()
+        .copied()
+        .find(|mode| supported.contains(mode))
+        .unwrap_or(PresentMode::Fifo)
+}
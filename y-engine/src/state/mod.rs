@@ -1,4 +1,12 @@
-use winit::{event::WindowEvent, event_loop::ActiveEventLoop};
+use winit::{
+    event::{DeviceEvent, DeviceId, WindowEvent},
+    event_loop::ActiveEventLoop,
+    window::WindowId,
+};
+
+use crate::{app::App, config::EngineConfig, error::EngineError, startup::StartupTasks};
+
+pub(crate) use starting::Waker;
 
 pub mod common;
 /// When the users app is running
@@ -6,22 +14,65 @@ pub(crate) mod running;
 /// Loading state (when splash screen is shown)
 mod starting;
 
-pub enum State {
+pub(crate) use starting::StateMsg;
+
+/// `E` is the app's [`App::UserEvent`].
+pub enum State<E: Send + 'static> {
     None,
-    Starting(starting::State),
-    Running(running::State),
+    Starting(Box<starting::State>),
+    Running(Box<running::State<E>>),
 }
 
-impl State {
-    pub fn new(event_loop: &ActiveEventLoop) -> Self {
-        Self::Starting(starting::State::new(event_loop))
+impl<E: Send + 'static> State<E> {
+    pub fn new(
+        event_loop: &ActiveEventLoop,
+        config: EngineConfig,
+        tasks: StartupTasks,
+        waker: Waker,
+    ) -> Result<Self, EngineError> {
+        Ok(Self::Starting(Box::new(starting::State::new(
+            event_loop, config, tasks, waker,
+        )?)))
     }
 
-    pub fn handle_window_event(&mut self, event_loop: &ActiveEventLoop, event: WindowEvent) {
+    pub fn handle_window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_id: WindowId,
+        event: WindowEvent,
+    ) -> Result<(), EngineError> {
         match self {
             Self::Starting(state) => state.handle_window_event(event_loop, event),
-            Self::Running(state) => state.handle_window_event(event_loop, event),
+            Self::Running(state) => {
+                state.handle_window_event(event_loop, window_id, event);
+                Ok(())
+            }
             Self::None => unreachable!(),
         }
     }
+
+    /// Device events are only forwarded to a running app.
+    pub fn handle_device_event(&mut self, device_id: DeviceId, event: DeviceEvent) {
+        if let Self::Running(state) = self {
+            state.handle_device_event(device_id, event);
+        }
+    }
+
+    /// The app, once it is running.
+    pub fn app_mut(&mut self) -> Option<&mut dyn App<UserEvent = E>> {
+        match self {
+            Self::Running(state) => Some(state.app_mut()),
+            _ => None,
+        }
+    }
+
+    /// Drops the current state in a safe order and leaves `State::None` behind.
+    pub fn shutdown(&mut self) {
+        match std::mem::replace(self, Self::None) {
+            Self::Running(state) => state.shutdown(),
+            // Drops the render core and the splash screen before the window.
+            Self::Starting(state) => drop(state),
+            Self::None => {}
+        }
+    }
 }
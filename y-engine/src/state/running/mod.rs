@@ -1,25 +1,168 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Instant};
 
-use winit::{event::WindowEvent, event_loop::ActiveEventLoop, window::Window};
+use winit::{
+    event::{DeviceEvent, DeviceId, WindowEvent},
+    event_loop::{ActiveEventLoop, ControlFlow},
+    window::{Window, WindowId},
+};
 
-use crate::app::App;
+use crate::{
+    app::App,
+    config::{apply_window_attributes, LoopConfig, LoopMode},
+    event::EventProxy,
+    util::time::{frame_duration, FixedTimestep, FrameClock, FrameLimiter},
+};
 
-use super::starting;
+use super::{common::surface::WindowSurface, starting};
 
-pub struct State {
-    app: Box<dyn App>,
-    _window: Arc<Window>,
+pub struct State<E: Send + 'static> {
+    app: Box<dyn App<UserEvent = E>>,
+    /// Shared with the app's render core, kept in sync with the window size.
+    surface: Option<Arc<WindowSurface>>,
+    /// Redraws are skipped while the window has a zero size.
+    minimized: bool,
+    window: Arc<Window>,
+    loop_config: LoopConfig,
+    clock: FrameClock,
+    fixed_timestep: FixedTimestep,
+    limiter: Option<FrameLimiter>,
 }
 
-impl State {
-    pub fn new<T: App + 'static>(old_state: starting::State) -> Self {
+/// Runs the fixed updates, update and redraw of a frame that starts at `now`.
+///
+/// Takes the clock and timestep separately from the state, so frames can be driven
+/// by a fake clock.
+pub(crate) fn run_frame<E: Send + 'static>(
+    app: &mut dyn App<UserEvent = E>,
+    clock: &mut FrameClock,
+    fixed_timestep: &mut FixedTimestep,
+    now: Instant,
+) {
+    let mut frame = clock.tick(now);
+    for _ in 0..fixed_timestep.advance(frame.delta) {
+        app.fixed_update(fixed_timestep.step());
+    }
+    frame.alpha = fixed_timestep.alpha();
+    app.update(frame.delta);
+    app.window_redraw(&frame);
+}
+
+impl<E: Send + 'static> State<E> {
+    pub fn new<T: App<UserEvent = E> + 'static>(
+        old_state: starting::State,
+        proxy: EventProxy<E>,
+    ) -> Self {
+        if old_state.splash.is_some() {
+            apply_window_attributes(&old_state.window, &old_state.config.window);
+        }
+        let render_core = old_state.render_core.unwrap();
+        // Resizes while starting are not handled, catch up with the current size.
+        let size = old_state.window.inner_size();
+        if let Some(surface) = &render_core.surface {
+            surface.resize(size.width, size.height);
+        }
         Self {
-            app: T::new(old_state.window.clone(), old_state.render_core.unwrap()),
-            _window: old_state.window,
+            surface: render_core.surface.clone(),
+            minimized: size.width == 0 || size.height == 0,
+            app: T::new(
+                old_state.window.clone(),
+                render_core,
+                old_state.results,
+                proxy,
+            ),
+            window: old_state.window,
+            fixed_timestep: FixedTimestep::new(
+                old_state.config.game_loop.fixed_step(),
+                old_state.config.game_loop.max_fixed_updates,
+            ),
+            limiter: old_state
+                .config
+                .game_loop
+                .frame_limit
+                .map(FrameLimiter::new),
+            clock: FrameClock::new(Instant::now(), old_state.config.game_loop.stats_frames),
+            loop_config: old_state.config.game_loop,
+        }
+    }
+
+    pub fn window(&self) -> &Arc<Window> {
+        &self.window
+    }
+
+    pub fn app_mut(&mut self) -> &mut dyn App<UserEvent = E> {
+        &mut *self.app
+    }
+
+    /// Drops the app (and with it its render core) before the window surface and the window.
+    pub fn shutdown(self) {
+        let Self {
+            app,
+            surface,
+            window,
+            ..
+        } = self;
+        drop(app);
+        drop(surface);
+        drop(window);
+    }
+
+    /// Called once per event loop iteration, schedules the next frame according to the loop mode.
+    pub fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        if self.minimized {
+            event_loop.set_control_flow(ControlFlow::Wait);
+            return;
+        }
+        match self.loop_config.mode {
+            LoopMode::Poll => {
+                event_loop.set_control_flow(ControlFlow::Poll);
+                self.window.request_redraw();
+            }
+            LoopMode::Wait => event_loop.set_control_flow(ControlFlow::Wait),
+            LoopMode::TargetFps(fps) => {
+                let next_frame = self.clock.last_tick() + frame_duration(fps);
+                if Instant::now() >= next_frame {
+                    event_loop.set_control_flow(ControlFlow::Wait);
+                    self.window.request_redraw();
+                } else {
+                    event_loop.set_control_flow(ControlFlow::WaitUntil(next_frame));
+                }
+            }
         }
     }
 
-    pub fn handle_window_event(&mut self, event_loop: &ActiveEventLoop, event: WindowEvent) {
+    fn frame(&mut self, now: Instant) {
+        run_frame(
+            &mut *self.app,
+            &mut self.clock,
+            &mut self.fixed_timestep,
+            now,
+        );
+    }
+
+    pub fn handle_device_event(&mut self, device_id: DeviceId, event: DeviceEvent) {
+        self.app.device_raw(device_id, &event);
+        match event {
+            DeviceEvent::Added => self.app.device_added(device_id),
+            DeviceEvent::Removed => self.app.device_removed(device_id),
+            DeviceEvent::MouseMotion { delta: (dx, dy) } => self.app.raw_mouse_motion(dx, dy),
+            DeviceEvent::MouseWheel { delta } => self.app.raw_mouse_wheel(delta),
+            DeviceEvent::Motion { axis, value } => self.app.raw_axis_motion(axis, value),
+            DeviceEvent::Button { button, state } => self.app.raw_button_input(button, state),
+            DeviceEvent::Key(event) => self.app.raw_key_input(event.physical_key, event.state),
+        }
+    }
+
+    pub fn handle_window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        if window_id != self.window.id() {
+            self.app
+                .secondary_window_event(window_id, event, event_loop);
+            return;
+        }
         if self.app.window_raw_before(&event, event_loop) {
             return;
         }
@@ -28,20 +171,33 @@ impl State {
                 self.app.window_close_requested(event_loop);
             }
             WindowEvent::Resized(size) => {
+                self.minimized = size.width == 0 || size.height == 0;
+                if let Some(surface) = &self.surface {
+                    surface.resize(size.width, size.height);
+                }
                 self.app.window_resized(size.width, size.height);
             }
-            WindowEvent::RedrawRequested => {
-                self.app.window_redraw();
+            WindowEvent::RedrawRequested if !self.minimized => {
+                let now = match &mut self.limiter {
+                    Some(limiter) => limiter.wait(),
+                    None => Instant::now(),
+                };
+                self.frame(now);
             }
             WindowEvent::MouseInput { state, button, .. } => {
-                self.app.mouse_button_input(*button, *state);
+                let reacquired = self
+                    .app
+                    .cursor_grab()
+                    .is_some_and(|cursor| cursor.handle_mouse_button_input(*button, *state));
+                if !reacquired {
+                    self.app.mouse_button_input(*button, *state);
+                }
             }
             WindowEvent::MouseWheel { delta, phase, .. } => {
                 self.app.mouse_wheel_input(*delta, *phase);
             }
             WindowEvent::KeyboardInput { event, .. } => {
-                self.app
-                    .keyboard_button_input(event.logical_key.clone(), event.state);
+                self.app.keyboard_button_input(event.clone().into());
             }
             WindowEvent::CursorMoved { position, .. } => {
                 self.app.cursor_moved(*position);
@@ -52,8 +208,174 @@ impl State {
             WindowEvent::CursorEntered { .. } => {
                 self.app.cursor_entered_window();
             }
+            WindowEvent::Destroyed => {
+                self.app.window_destroyed();
+            }
+            WindowEvent::Moved(position) => {
+                self.app.window_moved(*position);
+            }
+            WindowEvent::Focused(focused) => {
+                if let Some(cursor) = self.app.cursor_grab() {
+                    cursor.handle_focus_changed(*focused);
+                }
+                if *focused {
+                    self.app.window_focused();
+                } else {
+                    self.app.window_unfocused();
+                }
+            }
+            WindowEvent::Occluded(occluded) => {
+                self.app.window_occluded(*occluded);
+            }
+            WindowEvent::ScaleFactorChanged {
+                scale_factor,
+                inner_size_writer,
+            } => {
+                self.app
+                    .window_scale_factor_changed(*scale_factor, inner_size_writer.clone());
+            }
+            WindowEvent::ThemeChanged(theme) => {
+                self.app.window_theme_changed(*theme);
+            }
+            WindowEvent::ActivationTokenDone { serial, token } => {
+                self.app
+                    .window_activation_token_done(*serial, token.clone());
+            }
+            WindowEvent::DroppedFile(path) => {
+                self.app.file_dropped(path);
+            }
+            WindowEvent::HoveredFile(path) => {
+                self.app.file_hovered(path);
+            }
+            WindowEvent::HoveredFileCancelled => {
+                self.app.file_hover_cancelled();
+            }
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.app.modifiers_changed(*modifiers);
+            }
+            WindowEvent::Ime(ime) => {
+                self.app.ime(ime.clone());
+            }
+            WindowEvent::Touch(touch) => {
+                self.app.touch(*touch);
+            }
+            WindowEvent::PinchGesture { delta, phase, .. } => {
+                self.app.pinch_gesture(*delta, *phase);
+            }
+            WindowEvent::PanGesture { delta, phase, .. } => {
+                self.app.pan_gesture(*delta, *phase);
+            }
+            WindowEvent::RotationGesture { delta, phase, .. } => {
+                self.app.rotation_gesture(*delta, *phase);
+            }
+            WindowEvent::DoubleTapGesture { .. } => {
+                self.app.double_tap_gesture();
+            }
+            WindowEvent::TouchpadPressure {
+                pressure, stage, ..
+            } => {
+                self.app.touchpad_pressure(*pressure, *stage);
+            }
+            WindowEvent::AxisMotion { axis, value, .. } => {
+                self.app.axis_motion(*axis, *value);
+            }
             _ => {}
         }
         self.app.window_raw_after(event, event_loop);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        error::EngineError, startup::StartupResults, state::common::render::RenderCore,
+        util::time::FrameTime,
+    };
+
+    #[derive(Debug, PartialEq)]
+    enum Call {
+        FixedUpdate(Duration),
+        Update(Duration),
+        Redraw { frame: u64, alpha: f32 },
+    }
+
+    #[derive(Default)]
+    struct Recorder {
+        calls: Vec<Call>,
+    }
+
+    impl App for Recorder {
+        type UserEvent = ();
+
+        fn new(_: Arc<Window>, _: RenderCore, _: StartupResults, _: EventProxy<()>) -> Box<Self> {
+            unreachable!("Not started by the engine")
+        }
+
+        fn startup_failed(_: &EngineError) {}
+
+        fn fixed_update(&mut self, step: Duration) {
+            self.calls.push(Call::FixedUpdate(step));
+        }
+
+        fn update(&mut self, dt: Duration) {
+            self.calls.push(Call::Update(dt));
+        }
+
+        fn window_redraw(&mut self, frame: &FrameTime) {
+            self.calls.push(Call::Redraw {
+                frame: frame.frame,
+                alpha: frame.alpha,
+            });
+        }
+    }
+
+    #[test]
+    fn frames_run_fixed_updates_then_update_then_redraw() {
+        let start = Instant::now();
+        let step = Duration::from_millis(10);
+        let mut app = Recorder::default();
+        let mut clock = FrameClock::new(start, 8);
+        let mut fixed_timestep = FixedTimestep::new(step, 3);
+
+        let ms = Duration::from_millis;
+        for now in [ms(25), ms(30), ms(34), ms(100)] {
+            run_frame(&mut app, &mut clock, &mut fixed_timestep, start + now);
+        }
+
+        assert_eq!(
+            app.calls,
+            [
+                Call::FixedUpdate(step),
+                Call::FixedUpdate(step),
+                Call::Update(ms(25)),
+                Call::Redraw {
+                    frame: 0,
+                    alpha: 0.5
+                },
+                Call::FixedUpdate(step),
+                Call::Update(ms(5)),
+                Call::Redraw {
+                    frame: 1,
+                    alpha: 0.0
+                },
+                Call::Update(ms(4)),
+                Call::Redraw {
+                    frame: 2,
+                    alpha: 0.4
+                },
+                // 66ms behind, capped at 3 steps, the rest is dropped.
+                Call::FixedUpdate(step),
+                Call::FixedUpdate(step),
+                Call::FixedUpdate(step),
+                Call::Update(ms(66)),
+                Call::Redraw {
+                    frame: 3,
+                    alpha: 0.0
+                },
+            ]
+        );
+    }
+}
@@ -1,6 +1,12 @@
-use std::{num::NonZeroU32, sync::Arc};
+use std::{
+    any::Any,
+    num::NonZeroU32,
+    panic::AssertUnwindSafe,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use image::RgbaImage;
+use splash::Splash;
 use winit::{
     dpi::{PhysicalPosition, PhysicalSize},
     event::WindowEvent,
@@ -8,70 +14,116 @@ use winit::{
     window::{Window, WindowAttributes, WindowLevel},
 };
 
+use crate::{
+    config::EngineConfig,
+    error::EngineError,
+    startup::{StartupResults, StartupTask, StartupTasks, TaskProgress},
+};
+
 use super::common::render::RenderCore;
 
-const SPLASH_IMAGE_RAW: &[u8] = include_bytes!("../../../assets/splashscreen.png");
+mod splash;
+
+/// Time between two frames of the splash fade-out.
+const FADE_FRAME_TIME: Duration = Duration::from_millis(16);
 
-mod init_gpu;
+/// Wakes up the event loop, so the state checks for messages of the init threads.
+pub(crate) type Waker = Arc<dyn Fn() + Send + Sync>;
+
+/// Index of the gpu initialization in `State::tasks`, the startup tasks of the app follow it.
+const GPU_TASK: usize = 0;
 
 pub enum StateMsg {
-    InitializedGpu {
-        instance: wgpu::Instance,
-        adapter: wgpu::Adapter,
-        device: wgpu::Device,
-        queue: wgpu::Queue,
-        surface: wgpu::Surface<'static>,
-        surface_config: wgpu::SurfaceConfiguration,
+    InitializedGpu(Box<RenderCore>),
+    InitFailed(EngineError),
+    TaskProgress {
+        task: usize,
+        progress: f32,
+    },
+    TaskStatus {
+        task: usize,
+        status: String,
+    },
+    TaskFinished {
+        task: usize,
+        result: Box<dyn Any + Send>,
     },
 }
 
+/// Progress of a single startup task, as shown on the splash screen.
+pub struct TaskState {
+    pub name: String,
+    pub progress: f32,
+    pub finished: bool,
+}
+
 pub struct State {
     pub msg_tx: crossbeam::channel::Sender<StateMsg>,
     pub msg_rx: crossbeam::channel::Receiver<StateMsg>,
 
     pub init_gpu_thread: Option<std::thread::JoinHandle<()>>,
+    pub task_threads: Vec<std::thread::JoinHandle<()>>,
 
     pub render_core: Option<RenderCore>,
+    pub config: EngineConfig,
 
-    pub splash_image: RgbaImage,
-    pub _softbuffer_context: softbuffer::Context<Arc<Window>>,
-    pub softbuffer_surface: softbuffer::Surface<Arc<Window>, Arc<Window>>,
+    pub tasks: Vec<TaskState>,
+    /// The last status reported by a task that is still running.
+    pub status: Option<(usize, String)>,
+    pub results: StartupResults,
+
+    /// None if the splash screen is disabled.
+    pub splash: Option<Splash>,
+    pub shown_at: Instant,
+    pub fade_started: Option<Instant>,
+    pub done: bool,
     pub window: Arc<Window>,
+    pub waker: Waker,
 }
 
 impl State {
-    pub fn new(event_loop: &ActiveEventLoop) -> Self {
+    pub fn new(
+        event_loop: &ActiveEventLoop,
+        config: EngineConfig,
+        tasks: StartupTasks,
+        waker: Waker,
+    ) -> Result<Self, EngineError> {
         event_loop.set_control_flow(ControlFlow::Wait);
 
-        let splash_image = image::load_from_memory(SPLASH_IMAGE_RAW)
-            .unwrap()
-            .to_rgba8();
-
-        let monitor_size = event_loop
-            .primary_monitor()
-            .unwrap_or(event_loop.available_monitors().next().unwrap())
-            .size();
-        let (window_width, window_height) =
-            (splash_image.width().max(4), splash_image.height().max(4));
-        let window = event_loop
-            .create_window(
-                WindowAttributes::default()
-                    .with_transparent(true)
-                    .with_decorations(false)
-                    .with_inner_size(PhysicalSize::new(window_width, window_height))
-                    .with_position(PhysicalPosition::new(
-                        (monitor_size.width - window_width) / 2,
-                        (monitor_size.height - window_height) / 2,
-                    ))
-                    .with_window_level(WindowLevel::AlwaysOnTop)
-                    .with_title("Y-ENGINE"),
-            )
-            .unwrap();
-        let window = Arc::new(window);
-
-        let softbuffer_context = softbuffer::Context::new(window.clone()).unwrap();
-        let softbuffer_surface =
-            softbuffer::Surface::new(&softbuffer_context, window.clone()).unwrap();
+        let (window, splash) = if config.splash.enabled {
+            let splash_image = config.splash.image.load()?;
+            let size = config.splash.size.unwrap_or(PhysicalSize::new(
+                splash_image.width(),
+                splash_image.height(),
+            ));
+            let (window_width, window_height) = (size.width.max(4), size.height.max(4));
+            let mut window_attributes = WindowAttributes::default()
+                .with_transparent(true)
+                .with_decorations(false)
+                .with_inner_size(PhysicalSize::new(window_width, window_height))
+                .with_window_level(if config.splash.always_on_top {
+                    WindowLevel::AlwaysOnTop
+                } else {
+                    WindowLevel::Normal
+                })
+                .with_title(&config.splash.title);
+            if let Some(monitor) = event_loop
+                .primary_monitor()
+                .or_else(|| event_loop.available_monitors().next())
+            {
+                let monitor_size = monitor.size();
+                window_attributes = window_attributes.with_position(PhysicalPosition::new(
+                    monitor_size.width.saturating_sub(window_width) / 2,
+                    monitor_size.height.saturating_sub(window_height) / 2,
+                ));
+            }
+            let window = Arc::new(event_loop.create_window(window_attributes)?);
+            let splash = Splash::new(window.clone(), splash_image, &config.splash)?;
+            (window, Some(splash))
+        } else {
+            let window = Arc::new(event_loop.create_window(config.window.clone())?);
+            (window, None)
+        };
 
         let (msg_tx, msg_rx) = crossbeam::channel::unbounded();
 
@@ -79,85 +131,209 @@ impl State {
             msg_tx,
             msg_rx,
             init_gpu_thread: None,
+            task_threads: Vec::new(),
             render_core: None,
-            splash_image,
-            _softbuffer_context: softbuffer_context,
-            softbuffer_surface,
+            config,
+            tasks: vec![TaskState {
+                name: "Initializing GPU".to_string(),
+                progress: 0.0,
+                finished: false,
+            }],
+            status: None,
+            results: StartupResults::default(),
+            splash,
+            shown_at: Instant::now(),
+            fade_started: None,
+            done: false,
             window,
+            waker,
         };
 
-        s.spawn_init_threads();
+        s.spawn_init_threads(tasks);
+        if let Some(splash) = &mut s.splash {
+            splash.set_progress(0.0, &s.tasks[GPU_TASK].name);
+        }
 
-        s
+        Ok(s)
     }
 
-    pub fn handle_window_event(&mut self, event_loop: &ActiveEventLoop, event: WindowEvent) {
-        while let Ok(msg) = self.msg_rx.try_recv() {
-            match msg {
-                StateMsg::InitializedGpu {
-                    instance,
-                    adapter,
-                    device,
-                    queue,
-                    surface,
-                    surface_config,
-                } => {
-                    assert!(self.render_core.is_none());
-                    self.render_core = Some(RenderCore {
-                        instance,
-                        adapter,
-                        device,
-                        queue,
-                        surface,
-                        surface_config,
-                    });
-                }
-            }
-            // So the engine checks if the transition from starting to running should happen
-            self.window.request_redraw();
-        }
+    pub fn handle_window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        event: WindowEvent,
+    ) -> Result<(), EngineError> {
         match event {
             WindowEvent::CloseRequested => {
                 event_loop.exit();
             }
             WindowEvent::Resized(size) => {
-                let (width, height) = (size.width, size.height);
-                if width == 0 || height == 0 {
-                    return;
-                }
+                let (Some(width), Some(height)) =
+                    (NonZeroU32::new(size.width), NonZeroU32::new(size.height))
+                else {
+                    return Ok(());
+                };
 
-                self.softbuffer_surface
-                    .resize(
-                        NonZeroU32::new(width).unwrap(),
-                        NonZeroU32::new(height).unwrap(),
-                    )
-                    .unwrap();
-                self.draw_y_engine_splash();
+                if let Some(splash) = &mut self.splash {
+                    splash.resize(width, height)?;
+                    splash.draw()?;
+                }
             }
             _ => {}
         }
+        Ok(())
     }
 
+    /// Called once per event loop iteration. Handles messages of the init threads
+    /// and drives the minimum splash duration and the fade-out.
+    pub fn update(&mut self, event_loop: &ActiveEventLoop) -> Result<(), EngineError> {
+        let mut progressed = false;
+        while let Ok(msg) = self.msg_rx.try_recv() {
+            match msg {
+                StateMsg::InitializedGpu(render_core) => {
+                    assert!(self.render_core.is_none());
+                    self.render_core = Some(*render_core);
+                    self.finish_task(GPU_TASK);
+                }
+                StateMsg::InitFailed(error) => return Err(error),
+                StateMsg::TaskProgress { task, progress } => {
+                    self.tasks[task].progress = progress;
+                }
+                StateMsg::TaskStatus { task, status } => {
+                    if !self.tasks[task].finished {
+                        self.status = Some((task, status));
+                    }
+                }
+                StateMsg::TaskFinished { task, result } => {
+                    self.results.insert(result);
+                    self.finish_task(task);
+                }
+            }
+            progressed = true;
+        }
+        if progressed && self.fade_started.is_none() {
+            let (progress, status) = (self.progress(), self.status_text().to_string());
+            if let Some(splash) = &mut self.splash {
+                splash.set_progress(progress, &status);
+                splash.draw()?;
+            }
+        }
+
+        if self.done || self.tasks.iter().any(|task| !task.finished) {
+            return Ok(());
+        }
+        let Some(splash) = &mut self.splash else {
+            self.done = true;
+            return Ok(());
+        };
+
+        let now = Instant::now();
+        let min_end = self.shown_at + self.config.splash.min_duration;
+        if now < min_end {
+            event_loop.set_control_flow(ControlFlow::WaitUntil(min_end));
+            return Ok(());
+        }
+        let Some(fade_out) = self.config.splash.fade_out else {
+            self.done = true;
+            return Ok(());
+        };
+        let fade_started = *self.fade_started.get_or_insert(now);
+        let progress = now.duration_since(fade_started).as_secs_f32() / fade_out.as_secs_f32();
+        if progress >= 1.0 || progress.is_nan() {
+            self.done = true;
+            return Ok(());
+        }
+        splash.draw_faded(1.0 - progress)?;
+        event_loop.set_control_flow(ControlFlow::WaitUntil(now + FADE_FRAME_TIME));
+        Ok(())
+    }
+
+    /// True once the gpu is ready, all startup tasks are done and the splash screen is done.
     pub fn finished(&self) -> bool {
-        self.render_core.is_some()
+        self.done
     }
 
-    fn draw_y_engine_splash(&mut self) {
-        let size = self.window.inner_size();
-        let mut buffer = self.softbuffer_surface.buffer_mut().unwrap();
-        for index in 0..(size.width * size.height) {
-            let color = self
-                .splash_image
-                .get_pixel(index % size.width, index / size.width);
-            buffer[index as usize] = u32::from_ne_bytes(color.0);
+    fn finish_task(&mut self, task: usize) {
+        self.tasks[task].progress = 1.0;
+        self.tasks[task].finished = true;
+        if matches!(self.status, Some((status_task, _)) if status_task == task) {
+            self.status = None;
         }
-        buffer.present().unwrap();
     }
 
-    fn spawn_init_threads(&mut self) {
+    /// Overall progress of all tasks, from 0.0 to 1.0.
+    fn progress(&self) -> f32 {
+        self.tasks.iter().map(|task| task.progress).sum::<f32>() / self.tasks.len() as f32
+    }
+
+    /// The last reported status, or the name of the first unfinished task.
+    fn status_text(&self) -> &str {
+        if let Some((_, status)) = &self.status {
+            return status;
+        }
+        self.tasks
+            .iter()
+            .find(|task| !task.finished)
+            .map_or("", |task| &task.name)
+    }
+
+    fn spawn_init_threads(&mut self, tasks: StartupTasks) {
         assert!(self.init_gpu_thread.is_none());
         let _window = self.window.clone();
-        let _msg_tx = self.msg_tx.clone();
-        self.init_gpu_thread = Some(std::thread::spawn(|| init_gpu::init_gpu(_msg_tx, _window)));
+        let _gpu_config = self.config.gpu.clone();
+        let thread = self.spawn_init_thread(move || match RenderCore::new(_window, &_gpu_config) {
+            Ok(render_core) => StateMsg::InitializedGpu(Box::new(render_core)),
+            Err(error) => StateMsg::InitFailed(error),
+        });
+        self.init_gpu_thread = Some(thread);
+
+        for StartupTask { name, run } in tasks.into_tasks() {
+            let task = self.tasks.len();
+            self.tasks.push(TaskState {
+                name: name.clone(),
+                progress: 0.0,
+                finished: false,
+            });
+            let progress = TaskProgress {
+                task,
+                msg_tx: self.msg_tx.clone(),
+                waker: self.waker.clone(),
+            };
+            let thread = self.spawn_init_thread(move || match run(&progress) {
+                Ok(result) => StateMsg::TaskFinished { task, result },
+                Err(error) => {
+                    StateMsg::InitFailed(EngineError::StartupTaskFailed { task: name, error })
+                }
+            });
+            self.task_threads.push(thread);
+        }
+    }
+
+    /// Runs `init` on a new thread and sends its message back, panics are reported as errors.
+    fn spawn_init_thread<F>(&self, init: F) -> std::thread::JoinHandle<()>
+    where
+        F: FnOnce() -> StateMsg + Send + 'static,
+    {
+        let waker = self.waker.clone();
+        let msg_tx = self.msg_tx.clone();
+        std::thread::spawn(move || {
+            let msg = match std::panic::catch_unwind(AssertUnwindSafe(init)) {
+                Ok(msg) => msg,
+                Err(panic) => {
+                    StateMsg::InitFailed(EngineError::InitThreadPanicked(panic_message(panic)))
+                }
+            };
+            let _ = msg_tx.send(msg);
+            waker();
+        })
+    }
+}
+
+fn panic_message(panic: Box<dyn Any + Send>) -> String {
+    if let Some(msg) = panic.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = panic.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "unknown panic".to_string()
     }
 }
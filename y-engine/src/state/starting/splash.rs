@@ -0,0 +1,274 @@
+use std::{num::NonZeroU32, sync::Arc};
+
+use font8x8::legacy::BASIC_LEGACY;
+use image::{imageops::FilterType, RgbaImage};
+use winit::window::Window;
+
+use crate::{
+    config::{SplashConfig, SplashScaling},
+    error::EngineError,
+};
+
+/// Distance of the progress bar and status text to the window border.
+const PROGRESS_MARGIN: u32 = 8;
+const PROGRESS_BAR_HEIGHT: u32 = 4;
+const GLYPH_SIZE: u32 = 8;
+
+/// The splash screen, drawn with softbuffer while the gpu is initialized.
+pub struct Splash {
+    image: RgbaImage,
+    scaling: SplashScaling,
+    background: [u8; 4],
+    show_progress: bool,
+    progress_color: [u8; 4],
+    progress: f32,
+    status: String,
+    /// The image scaled to the current window size, already in softbuffer's pixel format.
+    frame: Vec<u32>,
+    frame_size: (u32, u32),
+    _context: softbuffer::Context<Arc<Window>>,
+    surface: softbuffer::Surface<Arc<Window>, Arc<Window>>,
+    window: Arc<Window>,
+}
+
+impl Splash {
+    pub fn new(
+        window: Arc<Window>,
+        image: RgbaImage,
+        config: &SplashConfig,
+    ) -> Result<Self, EngineError> {
+        let context = softbuffer::Context::new(window.clone())?;
+        let surface = softbuffer::Surface::new(&context, window.clone())?;
+        Ok(Self {
+            image,
+            scaling: config.scaling,
+            background: config.background,
+            show_progress: config.show_progress,
+            progress_color: config.progress_color,
+            progress: 0.0,
+            status: String::new(),
+            frame: Vec::new(),
+            frame_size: (0, 0),
+            _context: context,
+            surface,
+            window,
+        })
+    }
+
+    pub fn resize(&mut self, width: NonZeroU32, height: NonZeroU32) -> Result<(), EngineError> {
+        self.surface.resize(width, height)?;
+        Ok(())
+    }
+
+    /// Sets the progress (0.0 to 1.0) and status text shown on the next draw.
+    pub fn set_progress(&mut self, progress: f32, status: &str) {
+        self.progress = progress;
+        self.status.clear();
+        self.status.push_str(status);
+    }
+
+    pub fn draw(&mut self) -> Result<(), EngineError> {
+        self.draw_faded(1.0)
+    }
+
+    /// Draws the splash with all pixels (including alpha) multiplied by `opacity`.
+    pub fn draw_faded(&mut self, opacity: f32) -> Result<(), EngineError> {
+        let size = self.window.inner_size();
+        if size.width == 0 || size.height == 0 {
+            return Ok(());
+        }
+        if self.frame_size != (size.width, size.height) {
+            self.frame = render_frame(
+                &self.image,
+                self.scaling,
+                self.background,
+                size.width,
+                size.height,
+            );
+            self.frame_size = (size.width, size.height);
+        }
+
+        let mut buffer = self.surface.buffer_mut()?;
+        // The buffer may still have the old size if the resize event has not arrived yet.
+        let len = buffer.len().min(self.frame.len());
+        buffer[..len].copy_from_slice(&self.frame[..len]);
+        if self.show_progress {
+            let mut canvas = Canvas {
+                pixels: &mut buffer[..len],
+                width: size.width,
+            };
+            draw_progress(
+                &mut canvas,
+                size.height,
+                self.progress,
+                &self.status,
+                self.progress_color,
+            );
+        }
+        if opacity < 1.0 {
+            let opacity = (opacity.max(0.0) * 256.0) as u32;
+            for pixel in buffer[..len].iter_mut() {
+                *pixel = fade(*pixel, opacity);
+            }
+        }
+        buffer.present()?;
+        Ok(())
+    }
+}
+
+/// Draws the progress bar at the bottom of the window and the status text above it.
+fn draw_progress(canvas: &mut Canvas, height: u32, progress: f32, status: &str, color: [u8; 4]) {
+    let bar_width = canvas.width.saturating_sub(2 * PROGRESS_MARGIN);
+    let bar_y = height.saturating_sub(PROGRESS_MARGIN + PROGRESS_BAR_HEIGHT);
+    let mut track_color = color;
+    track_color[3] /= 4;
+    canvas.fill_rect(
+        PROGRESS_MARGIN,
+        bar_y,
+        bar_width,
+        PROGRESS_BAR_HEIGHT,
+        track_color,
+    );
+    canvas.fill_rect(
+        PROGRESS_MARGIN,
+        bar_y,
+        (bar_width as f32 * progress) as u32,
+        PROGRESS_BAR_HEIGHT,
+        color,
+    );
+
+    let text_y = bar_y.saturating_sub(PROGRESS_MARGIN / 2 + GLYPH_SIZE);
+    let max_chars = (bar_width / GLYPH_SIZE) as usize;
+    for (i, c) in status.chars().take(max_chars).enumerate() {
+        let glyph = BASIC_LEGACY
+            .get(c as usize)
+            .unwrap_or(&BASIC_LEGACY['?' as usize]);
+        let x = PROGRESS_MARGIN + i as u32 * GLYPH_SIZE;
+        for (row, bits) in glyph.iter().enumerate() {
+            for column in 0..GLYPH_SIZE {
+                if bits & (1 << column) != 0 {
+                    canvas.fill_rect(x + column, text_y + row as u32, 1, 1, color);
+                }
+            }
+        }
+    }
+}
+
+/// A packed pixel buffer that can be drawn onto.
+struct Canvas<'a> {
+    pixels: &'a mut [u32],
+    width: u32,
+}
+
+impl Canvas<'_> {
+    /// Blends `color` onto the rectangle, parts outside of the canvas are skipped.
+    fn fill_rect(&mut self, x: u32, y: u32, width: u32, height: u32, color: [u8; 4]) {
+        for row in y..y + height {
+            for column in x..(x + width).min(self.width) {
+                let index = (row * self.width + column) as usize;
+                if let Some(pixel) = self.pixels.get_mut(index) {
+                    *pixel = pack(blend(color, unpack(*pixel)));
+                }
+            }
+        }
+    }
+}
+
+/// Fits the image into a `width` x `height` frame according to `scaling`.
+fn render_frame(
+    image: &RgbaImage,
+    scaling: SplashScaling,
+    background: [u8; 4],
+    width: u32,
+    height: u32,
+) -> Vec<u32> {
+    let (image_width, image_height) = (image.width().max(1), image.height().max(1));
+    let scale_x = width as f32 / image_width as f32;
+    let scale_y = height as f32 / image_height as f32;
+    let (scaled_width, scaled_height) = match scaling {
+        SplashScaling::Native => (image_width, image_height),
+        SplashScaling::Fit => scaled_size(image_width, image_height, scale_x.min(scale_y)),
+        SplashScaling::Fill => scaled_size(image_width, image_height, scale_x.max(scale_y)),
+        SplashScaling::Stretch => (width, height),
+    };
+    let scaled;
+    let image = if (scaled_width, scaled_height) == (image.width(), image.height()) {
+        image
+    } else {
+        scaled = image::imageops::resize(image, scaled_width, scaled_height, FilterType::Triangle);
+        &scaled
+    };
+
+    // Offset of the image inside the frame, negative if the image is cropped.
+    let offset_x = (i64::from(width) - i64::from(scaled_width)) / 2;
+    let offset_y = (i64::from(height) - i64::from(scaled_height)) / 2;
+
+    let mut frame = vec![pack(background); (width * height) as usize];
+    for y in 0..height {
+        let image_y = i64::from(y) - offset_y;
+        if image_y < 0 || image_y >= i64::from(image.height()) {
+            continue;
+        }
+        for x in 0..width {
+            let image_x = i64::from(x) - offset_x;
+            if image_x < 0 || image_x >= i64::from(image.width()) {
+                continue;
+            }
+            let pixel = image.get_pixel(image_x as u32, image_y as u32).0;
+            frame[(y * width + x) as usize] = pack(blend(pixel, background));
+        }
+    }
+    frame
+}
+
+fn scaled_size(width: u32, height: u32, scale: f32) -> (u32, u32) {
+    (
+        ((width as f32 * scale).round() as u32).max(1),
+        ((height as f32 * scale).round() as u32).max(1),
+    )
+}
+
+/// Draws `over` on top of `under` (straight alpha).
+fn blend(over: [u8; 4], under: [u8; 4]) -> [u8; 4] {
+    let over_alpha = f32::from(over[3]) / 255.0;
+    let under_alpha = f32::from(under[3]) / 255.0 * (1.0 - over_alpha);
+    let alpha = over_alpha + under_alpha;
+    if alpha <= 0.0 {
+        return [0; 4];
+    }
+    let channel = |i: usize| {
+        ((f32::from(over[i]) * over_alpha + f32::from(under[i]) * under_alpha) / alpha).round()
+            as u8
+    };
+    [
+        channel(0),
+        channel(1),
+        channel(2),
+        (alpha * 255.0).round() as u8,
+    ]
+}
+
+/// Multiplies every channel of a packed pixel by `opacity / 256`.
+fn fade(pixel: u32, opacity: u32) -> u32 {
+    let mut out = 0;
+    for shift in [0, 8, 16, 24] {
+        let channel = (pixel >> shift) & 0xFF;
+        out |= ((channel * opacity) >> 8) << shift;
+    }
+    out
+}
+
+fn unpack(pixel: u32) -> [u8; 4] {
+    [
+        (pixel >> 16) as u8,
+        (pixel >> 8) as u8,
+        pixel as u8,
+        (pixel >> 24) as u8,
+    ]
+}
+
+/// Packs a pixel into softbuffer's 0RGB format, alpha goes into the
+/// upper byte for platforms that support transparent windows.
+fn pack([r, g, b, a]: [u8; 4]) -> u32 {
+    u32::from(a) << 24 | u32::from(r) << 16 | u32::from(g) << 8 | u32::from(b)
+}
@@ -0,0 +1,551 @@
+use std::{collections::BTreeMap, error::Error, fmt, hash::Hash, str::FromStr};
+
+use rustc_hash::FxHashMap;
+use serde::{
+    de::{value::StrDeserializer, IntoDeserializer},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use winit::{
+    event::MouseButton,
+    keyboard::{Key, KeyCode, NativeKey},
+};
+
+use super::input::InputManager;
+
+/// An input that actions and axes can be bound to.
+///
+/// Bindings have a stable text representation, used for [`BindingProfile`]s:
+/// named keys by their name (`Space`, `ArrowUp`, `F1`), character keys as the character
+/// (`w`, `1`), physical keys as `Physical:<key code>` (`Physical:KeyW`),
+/// `Mouse:Left`/`Right`/`Middle`/`Back`/`Forward`/`<index>` and `Wheel:Up`/`Down`/`Left`/`Right`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Binding {
+    /// Matches the logical key the pressed key produced, which depends on the layout
+    /// and modifiers (Shift turns `a` into `A`).
+    Key(Key),
+    /// Matches the key at this position on the keyboard, regardless of the layout.
+    PhysicalKey(KeyCode),
+    MouseButton(MouseButton),
+    /// Active for a frame when the wheel was scrolled in this direction.
+    Wheel(WheelDirection),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WheelDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+#[derive(Debug, Default)]
+struct ActionState {
+    bindings: Vec<Binding>,
+    pressed: bool,
+    was_pressed: bool,
+}
+
+#[derive(Debug, Default)]
+struct AxisState {
+    negative: Vec<Binding>,
+    positive: Vec<Binding>,
+    value: f32,
+}
+
+/// An action or an axis, e.g. the users of a binding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BindingTarget<A, X> {
+    Action(A),
+    Axis(X),
+}
+
+/// A binding that is used by more than one action or axis.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindingConflict<A, X> {
+    pub binding: Binding,
+    pub targets: Vec<BindingTarget<A, X>>,
+}
+
+/// The bindings of all actions and axes of an [`ActionMap`], to save and load them.
+/// Sorted by action and axis, so saved profiles are stable.
+///
+/// ```rust
+/// # use serde::{Deserialize, Serialize};
+/// # use winit::keyboard::{Key, NamedKey};
+/// # use y_engine::util::action::{ActionMap, Binding, BindingProfile};
+/// #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+/// enum Action {
+///     Jump,
+/// }
+///
+/// let mut actions: ActionMap<Action> = ActionMap::new();
+/// actions.bind(Action::Jump, Binding::Key(Key::Named(NamedKey::Space)));
+/// actions.store_defaults();
+///
+/// let saved = actions.to_profile().to_ron().unwrap();
+/// assert!(saved.contains(r#""Space""#));
+///
+/// actions.set_bindings(Action::Jump, vec!["Mouse:Left".parse().unwrap()]);
+/// actions.apply_profile(&BindingProfile::from_ron(&saved).unwrap());
+/// assert_eq!(actions.bindings(&Action::Jump), ["Space".parse().unwrap()]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "A: Serialize, X: Serialize",
+    deserialize = "A: Deserialize<'de>, X: Deserialize<'de>"
+))]
+pub struct BindingProfile<A: Ord, X: Ord> {
+    #[serde(default)]
+    pub actions: BTreeMap<A, Vec<Binding>>,
+    #[serde(default)]
+    pub axes: BTreeMap<X, AxisBindings>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct AxisBindings {
+    pub negative: Vec<Binding>,
+    pub positive: Vec<Binding>,
+}
+
+impl<A: Ord, X: Ord> Default for BindingProfile<A, X> {
+    fn default() -> Self {
+        Self {
+            actions: BTreeMap::new(),
+            axes: BTreeMap::new(),
+        }
+    }
+}
+
+impl<A: Ord + Serialize, X: Ord + Serialize> BindingProfile<A, X> {
+    pub fn to_ron(&self) -> Result<String, ron::Error> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+    }
+}
+
+impl<'de, A: Ord + Deserialize<'de>, X: Ord + Deserialize<'de>> BindingProfile<A, X> {
+    pub fn from_ron(ron: &'de str) -> Result<Self, ron::error::SpannedError> {
+        ron::from_str(ron)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Defaults<A, X> {
+    actions: FxHashMap<A, Vec<Binding>>,
+    axes: FxHashMap<X, AxisBindings>,
+}
+
+/// Maps named actions (`Jump`, `Fire`) and axes (`MoveX`) to bindings, on top of an
+/// [`InputManager`]. Bindings can be changed at any time.
+///
+/// Like [`crate::util::registry::Registry`], it is meant to be used with enums of IDs.
+/// Call [`ActionMap::update`] once per frame, before querying it and before
+/// [`InputManager::end_frame`].
+///
+/// ```rust,no_run
+/// # use winit::{event::MouseButton, keyboard::{Key, KeyCode, NamedKey}};
+/// # use y_engine::util::{action::{ActionMap, Binding, WheelDirection}, input::InputManager};
+/// #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// enum Action {
+///     Jump,
+///     Fire,
+///     NextWeapon,
+/// }
+///
+/// #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// enum Axis {
+///     MoveX,
+/// }
+///
+/// let mut actions: ActionMap<Action, Axis> = ActionMap::new();
+/// actions.bind(Action::Jump, Binding::Key(Key::Named(NamedKey::Space)));
+/// actions.bind(Action::Fire, Binding::MouseButton(MouseButton::Left));
+/// actions.bind(Action::NextWeapon, Binding::Wheel(WheelDirection::Up));
+/// actions.bind_axis(
+///     Axis::MoveX,
+///     Binding::PhysicalKey(KeyCode::KeyA),
+///     Binding::PhysicalKey(KeyCode::KeyD),
+/// );
+///
+/// # let mut input = InputManager::default();
+/// // Once per frame:
+/// actions.update(&input);
+/// if actions.action_just_pressed(&Action::Jump) {
+///     // ...
+/// }
+/// let move_x = actions.axis_value(&Axis::MoveX);
+/// input.end_frame();
+/// ```
+#[derive(Debug)]
+pub struct ActionMap<A: Hash + Eq, X: Hash + Eq = A> {
+    actions: FxHashMap<A, ActionState>,
+    axes: FxHashMap<X, AxisState>,
+    /// Set by [`ActionMap::store_defaults`].
+    defaults: Option<Defaults<A, X>>,
+    /// [`InputManager::total_scroll`] at the last update.
+    last_scroll: (f64, f64),
+}
+
+impl<A: Hash + Eq, X: Hash + Eq> Default for ActionMap<A, X> {
+    fn default() -> Self {
+        Self {
+            actions: FxHashMap::default(),
+            axes: FxHashMap::default(),
+            defaults: None,
+            last_scroll: (0.0, 0.0),
+        }
+    }
+}
+
+impl<A: Hash + Eq, X: Hash + Eq> ActionMap<A, X> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a binding to the action, an action is pressed if any of its bindings is.
+    pub fn bind(&mut self, action: A, binding: Binding) {
+        let state = self.actions.entry(action).or_default();
+        if !state.bindings.contains(&binding) {
+            state.bindings.push(binding);
+        }
+    }
+
+    /// Does not panic.
+    pub fn unbind(&mut self, action: &A, binding: &Binding) {
+        if let Some(state) = self.actions.get_mut(action) {
+            state.bindings.retain(|b| b != binding);
+        }
+    }
+
+    /// Replaces all bindings of the action.
+    pub fn set_bindings(&mut self, action: A, bindings: Vec<Binding>) {
+        self.actions.entry(action).or_default().bindings = bindings;
+    }
+
+    pub fn bindings(&self, action: &A) -> &[Binding] {
+        self.actions
+            .get(action)
+            .map_or(&[], |state| &state.bindings)
+    }
+
+    /// Adds a pair of bindings to the axis. Its value is -1.0 while `negative` is active,
+    /// 1.0 while `positive` is active and 0.0 if both or none are.
+    pub fn bind_axis(&mut self, axis: X, negative: Binding, positive: Binding) {
+        let state = self.axes.entry(axis).or_default();
+        state.negative.push(negative);
+        state.positive.push(positive);
+    }
+
+    /// Replaces all bindings of the axis.
+    pub fn set_axis_bindings(&mut self, axis: X, negative: Vec<Binding>, positive: Vec<Binding>) {
+        let state = self.axes.entry(axis).or_default();
+        state.negative = negative;
+        state.positive = positive;
+    }
+
+    /// The negative and positive bindings of the axis.
+    pub fn axis_bindings(&self, axis: &X) -> (&[Binding], &[Binding]) {
+        self.axes
+            .get(axis)
+            .map_or((&[], &[]), |state| (&state.negative, &state.positive))
+    }
+
+    /// Does not panic.
+    pub fn clear_bindings(&mut self, action: &A) {
+        if let Some(state) = self.actions.get_mut(action) {
+            state.bindings.clear();
+        }
+    }
+
+    /// Does not panic.
+    pub fn clear_axis_bindings(&mut self, axis: &X) {
+        if let Some(state) = self.axes.get_mut(axis) {
+            state.negative.clear();
+            state.positive.clear();
+        }
+    }
+
+    /// Reads the current input state, call it once per frame before
+    /// [`InputManager::end_frame`]. Bindings pressed and released within the frame
+    /// are active for it, so short taps are not lost.
+    pub fn update(&mut self, input: &InputManager) {
+        let total_scroll = input.total_scroll();
+        let scroll = (
+            total_scroll.0 - self.last_scroll.0,
+            total_scroll.1 - self.last_scroll.1,
+        );
+        self.last_scroll = total_scroll;
+
+        let is_active = |binding: &Binding| is_active(binding, input, scroll);
+        for state in self.actions.values_mut() {
+            state.was_pressed = state.pressed;
+            state.pressed = state.bindings.iter().any(is_active);
+        }
+        for state in self.axes.values_mut() {
+            let negative = state.negative.iter().any(is_active);
+            let positive = state.positive.iter().any(is_active);
+            state.value = f32::from(u8::from(positive)) - f32::from(u8::from(negative));
+        }
+    }
+
+    /// Does not panic, unknown actions are never pressed.
+    pub fn is_action_pressed(&self, action: &A) -> bool {
+        self.actions.get(action).is_some_and(|state| state.pressed)
+    }
+
+    /// True for the first frame the action is pressed.
+    pub fn action_just_pressed(&self, action: &A) -> bool {
+        self.actions
+            .get(action)
+            .is_some_and(|state| state.pressed && !state.was_pressed)
+    }
+
+    /// True for the first frame the action is no longer pressed.
+    pub fn action_just_released(&self, action: &A) -> bool {
+        self.actions
+            .get(action)
+            .is_some_and(|state| !state.pressed && state.was_pressed)
+    }
+
+    /// From -1.0 to 1.0, 0.0 for unknown axes.
+    pub fn axis_value(&self, axis: &X) -> f32 {
+        self.axes.get(axis).map_or(0.0, |state| state.value)
+    }
+}
+
+fn is_active(binding: &Binding, input: &InputManager, scroll: (f64, f64)) -> bool {
+    match binding {
+        Binding::Key(key) => {
+            input.is_logical_key_pressed(key) || input.logical_key_just_pressed(key)
+        }
+        Binding::PhysicalKey(key) => input.is_key_pressed(*key) || input.key_just_pressed(*key),
+        Binding::MouseButton(button) => {
+            input.is_mouse_button_pressed(button) || input.mouse_button_just_pressed(button)
+        }
+        Binding::Wheel(WheelDirection::Up) => scroll.1 > 0.0,
+        Binding::Wheel(WheelDirection::Down) => scroll.1 < 0.0,
+        Binding::Wheel(WheelDirection::Left) => scroll.0 < 0.0,
+        Binding::Wheel(WheelDirection::Right) => scroll.0 > 0.0,
+    }
+}
+
+impl<A: Hash + Eq + Clone, X: Hash + Eq + Clone> ActionMap<A, X> {
+    /// Remembers the current bindings as the defaults, see [`ActionMap::restore_defaults`].
+    pub fn store_defaults(&mut self) {
+        let actions = self
+            .actions
+            .iter()
+            .map(|(action, state)| (action.clone(), state.bindings.clone()))
+            .collect();
+        let axes = self
+            .axes
+            .iter()
+            .map(|(axis, state)| (axis.clone(), axis_bindings(state)))
+            .collect();
+        self.defaults = Some(Defaults { actions, axes });
+    }
+
+    /// Resets all bindings to the ones stored with [`ActionMap::store_defaults`].
+    /// Actions and axes without defaults lose their bindings.
+    ///
+    /// Panics if no defaults were stored.
+    pub fn restore_defaults(&mut self) {
+        let Defaults { actions, axes } = self
+            .defaults
+            .clone()
+            .expect("No default bindings stored, call store_defaults first.");
+        for state in self.actions.values_mut() {
+            state.bindings.clear();
+        }
+        for state in self.axes.values_mut() {
+            state.negative.clear();
+            state.positive.clear();
+        }
+        for (action, bindings) in actions {
+            self.set_bindings(action, bindings);
+        }
+        for (axis, bindings) in axes {
+            self.set_axis_bindings(axis, bindings.negative, bindings.positive);
+        }
+    }
+
+    /// All actions and axes that use the binding, e.g. to warn before rebinding.
+    pub fn targets_of(&self, binding: &Binding) -> Vec<BindingTarget<A, X>> {
+        let actions = self
+            .actions
+            .iter()
+            .filter(|(_, state)| state.bindings.contains(binding))
+            .map(|(action, _)| BindingTarget::Action(action.clone()));
+        let axes = self
+            .axes
+            .iter()
+            .filter(|(_, state)| {
+                state.negative.contains(binding) || state.positive.contains(binding)
+            })
+            .map(|(axis, _)| BindingTarget::Axis(axis.clone()));
+        actions.chain(axes).collect()
+    }
+
+    /// All bindings that are used by more than one action or axis, sorted by their text.
+    pub fn conflicts(&self) -> Vec<BindingConflict<A, X>> {
+        let mut bindings: Vec<&Binding> = self
+            .actions
+            .values()
+            .flat_map(|state| &state.bindings)
+            .chain(
+                self.axes
+                    .values()
+                    .flat_map(|state| state.negative.iter().chain(&state.positive)),
+            )
+            .collect();
+        bindings.sort_by_cached_key(|binding| binding.to_string());
+        bindings.dedup();
+        bindings
+            .into_iter()
+            .filter_map(|binding| {
+                let targets = self.targets_of(binding);
+                (targets.len() > 1).then(|| BindingConflict {
+                    binding: binding.clone(),
+                    targets,
+                })
+            })
+            .collect()
+    }
+}
+
+impl<A: Hash + Eq + Clone + Ord, X: Hash + Eq + Clone + Ord> ActionMap<A, X> {
+    pub fn to_profile(&self) -> BindingProfile<A, X> {
+        BindingProfile {
+            actions: self
+                .actions
+                .iter()
+                .map(|(action, state)| (action.clone(), state.bindings.clone()))
+                .collect(),
+            axes: self
+                .axes
+                .iter()
+                .map(|(axis, state)| (axis.clone(), axis_bindings(state)))
+                .collect(),
+        }
+    }
+
+    /// Replaces the bindings of all actions and axes in the profile.
+    /// The ones not in it (e.g. added after the profile was saved) keep their bindings.
+    pub fn apply_profile(&mut self, profile: &BindingProfile<A, X>) {
+        for (action, bindings) in &profile.actions {
+            self.set_bindings(action.clone(), bindings.clone());
+        }
+        for (axis, bindings) in &profile.axes {
+            self.set_axis_bindings(
+                axis.clone(),
+                bindings.negative.clone(),
+                bindings.positive.clone(),
+            );
+        }
+    }
+}
+
+fn axis_bindings(state: &AxisState) -> AxisBindings {
+    AxisBindings {
+        negative: state.negative.clone(),
+        positive: state.positive.clone(),
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Key(Key::Named(key)) => write!(f, "{key:?}"),
+            Self::Key(Key::Character(character)) => f.write_str(character),
+            Self::Key(Key::Dead(Some(character))) => write!(f, "Dead:{character}"),
+            Self::Key(Key::Dead(None)) => f.write_str("Dead"),
+            Self::Key(Key::Unidentified(_)) => f.write_str("Unidentified"),
+            Self::PhysicalKey(key) => write!(f, "Physical:{key:?}"),
+            Self::MouseButton(MouseButton::Other(index)) => write!(f, "Mouse:{index}"),
+            Self::MouseButton(button) => write!(f, "Mouse:{button:?}"),
+            Self::Wheel(direction) => write!(f, "Wheel:{direction:?}"),
+        }
+    }
+}
+
+impl FromStr for Binding {
+    type Err = ParseBindingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseBindingError(s.to_string());
+        if let Some(button) = s.strip_prefix("Mouse:") {
+            let button = match button {
+                "Left" => MouseButton::Left,
+                "Right" => MouseButton::Right,
+                "Middle" => MouseButton::Middle,
+                "Back" => MouseButton::Back,
+                "Forward" => MouseButton::Forward,
+                index => MouseButton::Other(index.parse().map_err(|_| error())?),
+            };
+            return Ok(Self::MouseButton(button));
+        }
+        if let Some(key) = s.strip_prefix("Physical:") {
+            return parse_variant(key).map(Self::PhysicalKey).ok_or_else(error);
+        }
+        if let Some(direction) = s.strip_prefix("Wheel:") {
+            let direction = match direction {
+                "Up" => WheelDirection::Up,
+                "Down" => WheelDirection::Down,
+                "Left" => WheelDirection::Left,
+                "Right" => WheelDirection::Right,
+                _ => return Err(error()),
+            };
+            return Ok(Self::Wheel(direction));
+        }
+        let key = match s {
+            "" => return Err(error()),
+            "Dead" => Key::Dead(None),
+            "Unidentified" => Key::Unidentified(NativeKey::Unidentified),
+            _ => {
+                if let Some(character) = s.strip_prefix("Dead:") {
+                    let mut chars = character.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(character), None) => Key::Dead(Some(character)),
+                        _ => return Err(error()),
+                    }
+                } else if let Some(key) = parse_variant(s) {
+                    Key::Named(key)
+                } else {
+                    Key::Character(s.into())
+                }
+            }
+        };
+        Ok(Self::Key(key))
+    }
+}
+
+/// Named keys and key codes are written with their variant names, parse them the same way
+/// serde does.
+fn parse_variant<'de, T: Deserialize<'de>>(s: &'de str) -> Option<T> {
+    let deserializer: StrDeserializer<serde::de::value::Error> = s.into_deserializer();
+    T::deserialize(deserializer).ok()
+}
+
+impl Serialize for Binding {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Binding {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// The text is not a valid [`Binding`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseBindingError(pub String);
+
+impl fmt::Display for ParseBindingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid binding: {:?}", self.0)
+    }
+}
+
+impl Error for ParseBindingError {}
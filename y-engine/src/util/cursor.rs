@@ -0,0 +1,148 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use winit::{
+    event::{ElementState, MouseButton},
+    window::{CursorGrabMode, Window},
+};
+
+/// Grabs and hides the cursor for relative mouse input (mouselook).
+///
+/// The cursor is locked in place where the platform supports it, and confined to the window
+/// otherwise. Return it from [`crate::app::App::cursor_grab`] and the engine releases the grab
+/// while the window is unfocused, and reacquires it with the next left click into the window.
+/// An [`InputManager`] that follows the grab reports relative motion while it is grabbed.
+///
+/// ```rust,no_run
+/// # use std::{sync::Arc, time::Duration};
+/// # use winit::window::Window;
+/// # use y_engine::util::{cursor::CursorGrab, input::InputManager};
+/// # struct MyApp { cursor: CursorGrab, input: InputManager }
+/// # impl MyApp {
+/// fn new(window: Arc<Window>) -> Self {
+///     let cursor = CursorGrab::new(window);
+///     let mut input = InputManager::default();
+///     input.follow_cursor_grab(&cursor);
+///     Self { cursor, input }
+/// }
+/// fn cursor_grab(&mut self) -> Option<&mut CursorGrab> {
+///     Some(&mut self.cursor)
+/// }
+/// fn raw_mouse_motion(&mut self, dx: f64, dy: f64) {
+///     self.input.handle_raw_mouse_motion(dx, dy);
+/// }
+/// fn update(&mut self, dt: Duration) {
+///     let delta = self.input.cursor_delta();
+///     // ... turn the camera by delta.x, delta.y ...
+/// }
+/// # }
+/// ```
+///
+/// [`InputManager`]: crate::util::input::InputManager
+#[derive(Debug)]
+pub struct CursorGrab {
+    window: Arc<Window>,
+    /// True if the app wants the cursor grabbed, even if it is currently released.
+    wanted: bool,
+    mode: Option<CursorGrabMode>,
+    /// Mirrors `mode.is_some()` for the input managers following the grab.
+    grabbed: Arc<AtomicBool>,
+    focused: bool,
+}
+
+impl CursorGrab {
+    pub fn new(window: Arc<Window>) -> Self {
+        let focused = window.has_focus();
+        Self {
+            window,
+            wanted: false,
+            mode: None,
+            grabbed: Arc::new(AtomicBool::new(false)),
+            focused,
+        }
+    }
+
+    /// Grabs and hides the cursor. If the window is unfocused, this happens once it is
+    /// focused and clicked.
+    ///
+    /// Returns true if the cursor is grabbed now.
+    pub fn grab(&mut self) -> bool {
+        self.wanted = true;
+        if self.focused {
+            self.acquire();
+        }
+        self.is_grabbed()
+    }
+
+    /// Releases and shows the cursor.
+    pub fn release(&mut self) {
+        self.wanted = false;
+        self.unacquire();
+    }
+
+    /// True if the app wants the cursor grabbed, even if it is released while unfocused.
+    pub fn is_wanted(&self) -> bool {
+        self.wanted
+    }
+
+    pub fn is_grabbed(&self) -> bool {
+        self.mode.is_some()
+    }
+
+    /// `Locked` or `Confined`, None if the cursor is not grabbed.
+    pub fn mode(&self) -> Option<CursorGrabMode> {
+        self.mode
+    }
+
+    /// Called by the engine for the main window if the app returns this grab from
+    /// [`crate::app::App::cursor_grab`].
+    pub fn handle_focus_changed(&mut self, focused: bool) {
+        self.focused = focused;
+        if !focused {
+            self.unacquire();
+        }
+    }
+
+    /// Reacquires the grab on a left click into the window. Called by the engine like
+    /// [`CursorGrab::handle_focus_changed`].
+    ///
+    /// Returns true if the click was used for that and should not be handled as input.
+    pub fn handle_mouse_button_input(&mut self, button: MouseButton, state: ElementState) -> bool {
+        if !self.wanted
+            || self.is_grabbed()
+            || button != MouseButton::Left
+            || state != ElementState::Pressed
+        {
+            return false;
+        }
+        // Clicks only reach unfocused windows on some platforms, and focus them.
+        self.focused = true;
+        self.acquire()
+    }
+
+    pub(crate) fn grabbed_flag(&self) -> Arc<AtomicBool> {
+        self.grabbed.clone()
+    }
+
+    fn acquire(&mut self) -> bool {
+        for mode in [CursorGrabMode::Locked, CursorGrabMode::Confined] {
+            if self.window.set_cursor_grab(mode).is_ok() {
+                self.window.set_cursor_visible(false);
+                self.mode = Some(mode);
+                self.grabbed.store(true, Ordering::Relaxed);
+                return true;
+            }
+        }
+        false
+    }
+
+    fn unacquire(&mut self) {
+        if self.mode.take().is_some() {
+            self.grabbed.store(false, Ordering::Relaxed);
+            let _ = self.window.set_cursor_grab(CursorGrabMode::None);
+            self.window.set_cursor_visible(true);
+        }
+    }
+}
@@ -1,36 +1,116 @@
-use std::{collections::VecDeque, time::Instant};
+use std::{
+    collections::{hash_map::Entry, VecDeque},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
 
-use rustc_hash::FxHashSet;
+use rustc_hash::{FxHashMap, FxHashSet};
 use winit::{
     dpi::PhysicalPosition,
-    event::{ElementState, MouseButton, MouseScrollDelta, TouchPhase},
-    keyboard::Key,
+    event::{ElementState, KeyEvent, Modifiers, MouseButton, MouseScrollDelta, TouchPhase},
+    keyboard::{Key, KeyCode, KeyLocation, ModifiersState, NativeKey, PhysicalKey, SmolStr},
 };
 
+use super::cursor::CursorGrab;
+
+/// Used to convert pixel scroll deltas (touchpads) to lines.
+const PIXELS_PER_LINE: f64 = 20.0;
+
+/// A key press or release, see [`crate::app::App::keyboard_button_input`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyInput {
+    /// The key's position on the keyboard, independent of the layout.
+    /// Use it for bindings like WASD that should work on AZERTY or Dvorak too.
+    pub physical_key: PhysicalKey,
+    /// The key's meaning with the current layout and modifiers, e.g. `"A"` with Shift held.
+    pub logical_key: Key,
+    /// The text the key produces, if any.
+    pub text: Option<SmolStr>,
+    pub location: KeyLocation,
+    pub state: ElementState,
+    /// True if this is a key repeat of a held key.
+    pub repeat: bool,
+}
+
+impl KeyInput {
+    /// None if the platform could not identify the physical key.
+    pub fn key_code(&self) -> Option<KeyCode> {
+        match self.physical_key {
+            PhysicalKey::Code(code) => Some(code),
+            PhysicalKey::Unidentified(_) => None,
+        }
+    }
+}
+
+impl From<KeyEvent> for KeyInput {
+    fn from(event: KeyEvent) -> Self {
+        Self {
+            physical_key: event.physical_key,
+            logical_key: event.logical_key,
+            text: event.text,
+            location: event.location,
+            state: event.state,
+            repeat: event.repeat,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum InputEvent {
     MousePressed(MouseButton),
     MouseReleased(MouseButton),
     MouseWheel(MouseScrollDelta, TouchPhase),
     CursorMoved(PhysicalPosition<f64>),
-    KeyPressed(Key),
-    KeyReleased(Key),
+    KeyPressed(KeyInput),
+    KeyReleased(KeyInput),
     CursorLeftWindow,
     CursorEnteredWindow,
+    ModifiersChanged(ModifiersState),
 }
 
+/// Tracks the keyboard and mouse state, feed it from the app's hooks.
+///
+/// Call [`InputManager::end_frame`] at the end of every frame, after all input was read;
+/// the `just_pressed`/`just_released` queries and press counts cover the frame up to then.
 #[derive(Debug)]
 pub struct InputManager {
     events: VecDeque<(Instant, InputEvent)>,
     max_events: usize,
     prune_cursor_moved_events: bool,
+    // Events that did not fit into the queue since creation.
+    dropped_events: u64,
 
-    pressed_keys: FxHashSet<Key>,
+    // Keys are tracked by their physical position, so a release always matches its press,
+    // even if the logical key changed in between (e.g. Shift turning "a" into "A").
+    // The logical key at the time of the press is kept as metadata.
+    pressed_keys: FxHashMap<KeyCode, Key>,
     pressed_mouse_buttons: FxHashSet<MouseButton>,
+    // Transitions since the last end_frame() call. A key pressed and released within
+    // one frame is in both sets.
+    keys_just_pressed: FxHashSet<KeyCode>,
+    logical_keys_just_pressed: FxHashSet<Key>,
+    keys_just_released: FxHashSet<KeyCode>,
+    mouse_buttons_just_pressed: FxHashSet<MouseButton>,
+    mouse_buttons_just_released: FxHashSet<MouseButton>,
+    key_press_counts: FxHashMap<KeyCode, u32>,
+    mouse_button_press_counts: FxHashMap<MouseButton, u32>,
+    modifiers: ModifiersState,
     cursor_position: PhysicalPosition<f64>,
     // This holds the position the cursor was at when the last
     // cursor_delta() call was made.
     cursor_position_for_delta: PhysicalPosition<f64>,
+    // Raw mouse motion accumulated since the last raw_mouse_delta() call.
+    raw_mouse_delta: (f64, f64),
+    // Scrolled lines since creation, never reset so multiple readers can diff it.
+    total_scroll: (f64, f64),
+    // While the cursor is locked, cursor_delta() reports the raw motion accumulated here.
+    // Set by follow_cursor_grab(), cursor_locked is its state as last seen.
+    cursor_grab: Option<Arc<AtomicBool>>,
+    cursor_locked: bool,
+    locked_cursor_delta: (f64, f64),
 }
 
 impl Default for InputManager {
@@ -39,11 +119,25 @@ impl Default for InputManager {
             events: VecDeque::new(),
             max_events: 32,
             prune_cursor_moved_events: true,
+            dropped_events: 0,
 
-            pressed_keys: FxHashSet::default(),
+            pressed_keys: FxHashMap::default(),
             pressed_mouse_buttons: FxHashSet::default(),
+            keys_just_pressed: FxHashSet::default(),
+            logical_keys_just_pressed: FxHashSet::default(),
+            keys_just_released: FxHashSet::default(),
+            mouse_buttons_just_pressed: FxHashSet::default(),
+            mouse_buttons_just_released: FxHashSet::default(),
+            key_press_counts: FxHashMap::default(),
+            mouse_button_press_counts: FxHashMap::default(),
+            modifiers: ModifiersState::empty(),
             cursor_position: PhysicalPosition::new(0.0, 0.0),
             cursor_position_for_delta: PhysicalPosition::new(0.0, 0.0),
+            raw_mouse_delta: (0.0, 0.0),
+            total_scroll: (0.0, 0.0),
+            cursor_grab: None,
+            cursor_locked: false,
+            locked_cursor_delta: (0.0, 0.0),
         }
     }
 }
@@ -54,29 +148,46 @@ impl InputManager {
             events: VecDeque::new(),
             max_events,
             prune_cursor_moved_events,
+            dropped_events: 0,
 
-            pressed_keys: FxHashSet::default(),
+            pressed_keys: FxHashMap::default(),
             pressed_mouse_buttons: FxHashSet::default(),
+            keys_just_pressed: FxHashSet::default(),
+            logical_keys_just_pressed: FxHashSet::default(),
+            keys_just_released: FxHashSet::default(),
+            mouse_buttons_just_pressed: FxHashSet::default(),
+            mouse_buttons_just_released: FxHashSet::default(),
+            key_press_counts: FxHashMap::default(),
+            mouse_button_press_counts: FxHashMap::default(),
+            modifiers: ModifiersState::empty(),
             cursor_position: PhysicalPosition::new(0.0, 0.0),
             cursor_position_for_delta: PhysicalPosition::new(0.0, 0.0),
+            raw_mouse_delta: (0.0, 0.0),
+            total_scroll: (0.0, 0.0),
+            cursor_grab: None,
+            cursor_locked: false,
+            locked_cursor_delta: (0.0, 0.0),
         }
     }
 
     pub fn handle_mouse_button_input(&mut self, button: MouseButton, state: ElementState) {
         let event = match state {
-            ElementState::Pressed => {
-                self.pressed_mouse_buttons.insert(button);
-                InputEvent::MousePressed(button)
-            }
-            ElementState::Released => {
-                self.pressed_mouse_buttons.remove(&button);
-                InputEvent::MouseReleased(button)
-            }
+            ElementState::Pressed => InputEvent::MousePressed(button),
+            ElementState::Released => InputEvent::MouseReleased(button),
         };
+        self.set_mouse_button_pressed(button, state.is_pressed());
         self.push_event(event);
     }
 
     pub fn handle_mouse_wheel_input(&mut self, delta: MouseScrollDelta, phase: TouchPhase) {
+        let (x, y) = match delta {
+            MouseScrollDelta::LineDelta(x, y) => (f64::from(x), f64::from(y)),
+            MouseScrollDelta::PixelDelta(position) => {
+                (position.x / PIXELS_PER_LINE, position.y / PIXELS_PER_LINE)
+            }
+        };
+        self.total_scroll.0 += x;
+        self.total_scroll.1 += y;
         self.push_event(InputEvent::MouseWheel(delta, phase));
     }
 
@@ -90,20 +201,56 @@ impl InputManager {
         self.push_event(InputEvent::CursorMoved(position));
     }
 
-    pub fn handle_keyboard_button_input(&mut self, key: Key, state: ElementState) {
-        let event = match state {
-            ElementState::Pressed => {
-                self.pressed_keys.insert(key.clone());
-                InputEvent::KeyPressed(key)
-            }
-            ElementState::Released => {
-                self.pressed_keys.remove(&key);
-                InputEvent::KeyReleased(key)
+    /// Keys the platform could not identify physically are only queued as events.
+    pub fn handle_keyboard_button_input(&mut self, input: KeyInput) {
+        if let Some(code) = input.key_code() {
+            match input.state {
+                ElementState::Pressed => self.press_key(code, input.logical_key.clone()),
+                ElementState::Released => self.release_key(code),
             }
+        }
+        let event = match input.state {
+            ElementState::Pressed => InputEvent::KeyPressed(input),
+            ElementState::Released => InputEvent::KeyReleased(input),
         };
         self.push_event(event);
     }
 
+    /// Feed this from [`crate::app::App::raw_mouse_motion`].
+    pub fn handle_raw_mouse_motion(&mut self, dx: f64, dy: f64) {
+        self.update_cursor_locked();
+        self.raw_mouse_delta.0 += dx;
+        self.raw_mouse_delta.1 += dy;
+        if self.cursor_locked {
+            self.locked_cursor_delta.0 += dx;
+            self.locked_cursor_delta.1 += dy;
+        }
+    }
+
+    /// While the cursor is grabbed, its position does not change (or is stuck at the window
+    /// border), so [`InputManager::cursor_delta`] reports the raw mouse motion instead.
+    pub fn follow_cursor_grab(&mut self, cursor: &CursorGrab) {
+        self.cursor_grab = Some(cursor.grabbed_flag());
+        self.update_cursor_locked();
+    }
+
+    /// True while the followed [`CursorGrab`] is grabbed.
+    pub fn is_cursor_locked(&self) -> bool {
+        self.cursor_grab
+            .as_ref()
+            .is_some_and(|grabbed| grabbed.load(Ordering::Relaxed))
+    }
+
+    /// Starts over with the deltas when the cursor got grabbed or released.
+    fn update_cursor_locked(&mut self) {
+        let locked = self.is_cursor_locked();
+        if locked != self.cursor_locked {
+            self.cursor_locked = locked;
+            self.locked_cursor_delta = (0.0, 0.0);
+            self.cursor_position_for_delta = self.cursor_position;
+        }
+    }
+
     pub fn handle_cursor_left_window(&mut self) {
         self.push_event(InputEvent::CursorLeftWindow);
     }
@@ -112,26 +259,107 @@ impl InputManager {
         self.push_event(InputEvent::CursorEnteredWindow);
     }
 
+    pub fn handle_modifiers_changed(&mut self, modifiers: Modifiers) {
+        self.modifiers = modifiers.state();
+        self.push_event(InputEvent::ModifiersChanged(self.modifiers));
+    }
+
+    /// The Ctrl, Shift, Alt and Super keys that are currently held, on either side.
+    pub fn modifiers(&self) -> ModifiersState {
+        self.modifiers
+    }
+
     pub fn pop_event(&mut self) -> Option<(Instant, InputEvent)> {
         self.events.pop_front()
     }
 
+    /// Number of events that were dropped because the queue was full (see `max_events`),
+    /// since the input manager was created.
+    pub fn dropped_events(&self) -> u64 {
+        self.dropped_events
+    }
+
     fn push_event(&mut self, event: InputEvent) {
         if self.events.len() >= self.max_events {
+            self.dropped_events += 1;
             return;
         }
         self.events.push_back((Instant::now(), event));
     }
 
-    pub fn is_key_pressed(&self, key: &Key) -> bool {
-        self.pressed_keys.contains(key)
+    /// Marks the end of a frame, clearing the `just_pressed`/`just_released` state and
+    /// the press counts. The pressed state is kept.
+    pub fn end_frame(&mut self) {
+        self.keys_just_pressed.clear();
+        self.logical_keys_just_pressed.clear();
+        self.keys_just_released.clear();
+        self.mouse_buttons_just_pressed.clear();
+        self.mouse_buttons_just_released.clear();
+        self.key_press_counts.clear();
+        self.mouse_button_press_counts.clear();
+    }
+
+    pub fn is_key_pressed(&self, key: KeyCode) -> bool {
+        self.pressed_keys.contains_key(&key)
+    }
+
+    /// True if any pressed key produced this logical key when it was pressed.
+    pub fn is_logical_key_pressed(&self, key: &Key) -> bool {
+        self.pressed_keys
+            .values()
+            .any(|logical_key| logical_key == key)
+    }
+
+    /// True if a key that produced this logical key was pressed this frame,
+    /// even if it was released again since. Key repeats do not count.
+    pub fn logical_key_just_pressed(&self, key: &Key) -> bool {
+        self.logical_keys_just_pressed.contains(key)
+    }
+
+    /// The logical key the pressed key produced when it was pressed, None if it is not pressed.
+    pub fn logical_key(&self, key: KeyCode) -> Option<&Key> {
+        self.pressed_keys.get(&key)
     }
 
-    pub fn set_key_pressed(&mut self, key: Key, pressed: bool) {
+    /// True if the key was pressed this frame, even if it was released again since.
+    /// Key repeats do not count.
+    pub fn key_just_pressed(&self, key: KeyCode) -> bool {
+        self.keys_just_pressed.contains(&key)
+    }
+
+    /// True if the key was released this frame, even if it was pressed again since.
+    pub fn key_just_released(&self, key: KeyCode) -> bool {
+        self.keys_just_released.contains(&key)
+    }
+
+    /// How often the key was pressed this frame, key repeats do not count.
+    pub fn key_press_count(&self, key: KeyCode) -> u32 {
+        self.key_press_counts.get(&key).copied().unwrap_or(0)
+    }
+
+    /// Changes to the pressed state count as transitions for this frame.
+    /// Keys pressed this way have an unidentified logical key.
+    pub fn set_key_pressed(&mut self, key: KeyCode, pressed: bool) {
         if pressed {
-            self.pressed_keys.insert(key);
+            self.press_key(key, Key::Unidentified(NativeKey::Unidentified));
         } else {
-            self.pressed_keys.remove(&key);
+            self.release_key(key);
+        }
+    }
+
+    /// Key repeats keep the logical key of the original press.
+    fn press_key(&mut self, key: KeyCode, logical_key: Key) {
+        if let Entry::Vacant(entry) = self.pressed_keys.entry(key) {
+            entry.insert(logical_key.clone());
+            *self.key_press_counts.entry(key).or_default() += 1;
+            self.keys_just_pressed.insert(key);
+            self.logical_keys_just_pressed.insert(logical_key);
+        }
+    }
+
+    fn release_key(&mut self, key: KeyCode) {
+        if self.pressed_keys.remove(&key).is_some() {
+            self.keys_just_released.insert(key);
         }
     }
 
@@ -139,14 +367,43 @@ impl InputManager {
         self.pressed_mouse_buttons.contains(button)
     }
 
+    /// True if the button was pressed this frame, even if it was released again since.
+    pub fn mouse_button_just_pressed(&self, button: &MouseButton) -> bool {
+        self.mouse_buttons_just_pressed.contains(button)
+    }
+
+    /// True if the button was released this frame, even if it was pressed again since.
+    pub fn mouse_button_just_released(&self, button: &MouseButton) -> bool {
+        self.mouse_buttons_just_released.contains(button)
+    }
+
+    /// How often the button was pressed this frame.
+    pub fn mouse_button_press_count(&self, button: &MouseButton) -> u32 {
+        self.mouse_button_press_counts
+            .get(button)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Changes to the pressed state count as transitions for this frame.
     pub fn set_mouse_button_pressed(&mut self, button: MouseButton, pressed: bool) {
         if pressed {
-            self.pressed_mouse_buttons.insert(button);
-        } else {
-            self.pressed_mouse_buttons.remove(&button);
+            if self.pressed_mouse_buttons.insert(button) {
+                *self.mouse_button_press_counts.entry(button).or_default() += 1;
+                self.mouse_buttons_just_pressed.insert(button);
+            }
+        } else if self.pressed_mouse_buttons.remove(&button) {
+            self.mouse_buttons_just_released.insert(button);
         }
     }
 
+    /// Lines scrolled horizontally and vertically since the input manager was created
+    /// (pixel deltas of touchpads are converted to lines).
+    /// Positive values are right and up. Compare two values to get the distance in between.
+    pub fn total_scroll(&self) -> (f64, f64) {
+        self.total_scroll
+    }
+
     pub fn cursor_position(&self) -> PhysicalPosition<f64> {
         self.cursor_position
     }
@@ -155,7 +412,13 @@ impl InputManager {
     ///
     /// The delta is calculated as the difference between the current cursor position
     /// and the cursor position at the last call to this function.
+    /// While the cursor is locked, it is the raw mouse motion since the last call instead.
     pub fn cursor_delta(&mut self) -> PhysicalPosition<f64> {
+        self.update_cursor_locked();
+        if self.cursor_locked {
+            let (dx, dy) = std::mem::take(&mut self.locked_cursor_delta);
+            return PhysicalPosition::new(dx, dy);
+        }
         let delta = PhysicalPosition::new(
             self.cursor_position.x - self.cursor_position_for_delta.x,
             self.cursor_position.y - self.cursor_position_for_delta.y,
@@ -163,4 +426,13 @@ impl InputManager {
         self.cursor_position_for_delta = self.cursor_position;
         delta
     }
+
+    /// Returns the raw mouse motion accumulated since the last call to this function.
+    ///
+    /// Unlike [`InputManager::cursor_delta`], it is not limited by the window or screen
+    /// borders and keeps working while the cursor is grabbed, which makes it the right
+    /// choice for mouselook.
+    pub fn raw_mouse_delta(&mut self) -> (f64, f64) {
+        std::mem::take(&mut self.raw_mouse_delta)
+    }
 }
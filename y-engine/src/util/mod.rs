@@ -1,2 +1,7 @@
+pub mod action;
+pub mod cursor;
 pub mod input;
 pub mod registry;
+pub mod scene;
+pub mod shortcut;
+pub mod time;
@@ -12,6 +12,8 @@ use rustc_hash::FxHashMap;
 ///
 /// The user is heavily adviced to do something like:
 /// ```rust,no_run
+/// # use y_engine::util::registry::Registry;
+/// # struct BindGroup;
 /// #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 /// enum ExampleId {
 ///     A,
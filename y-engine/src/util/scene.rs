@@ -0,0 +1,499 @@
+use std::{
+    ops::{Deref, DerefMut},
+    time::Duration,
+};
+
+use winit::event::{DeviceEvent, DeviceId, WindowEvent};
+
+use super::time::FrameTime;
+
+/// A scene (main menu, loading screen, gameplay, pause overlay, ...) on a [`SceneStack`].
+///
+/// `C` is the state all scenes share, e.g. the render core and input manager.
+/// All hooks get it through a [`SceneContext`], which also lets the scene push, pop
+/// or replace scenes.
+#[allow(unused_variables)]
+pub trait Scene<C> {
+    /// The scene was pushed onto the stack.
+    fn enter(&mut self, ctx: &mut SceneContext<C>) {}
+    /// The scene was popped off the stack (or replaced).
+    fn exit(&mut self, ctx: &mut SceneContext<C>) {}
+    /// Another scene was pushed on top of this one.
+    fn pause(&mut self, ctx: &mut SceneContext<C>) {}
+    /// The scene on top of this one was popped.
+    fn resume(&mut self, ctx: &mut SceneContext<C>) {}
+
+    /// Only called on the top scene.
+    fn fixed_update(&mut self, ctx: &mut SceneContext<C>, step: Duration) {}
+    /// Only called on the top scene.
+    fn update(&mut self, ctx: &mut SceneContext<C>, dt: Duration) {}
+    /// Called from the bottom to the top for all scenes above (and including)
+    /// the topmost opaque one.
+    fn render(&mut self, ctx: &mut SceneContext<C>, frame: &FrameTime) {}
+    /// If false, the scenes below are rendered first (e.g. for overlays).
+    fn is_opaque(&self) -> bool {
+        true
+    }
+
+    /// Return false to let the event fall through to the scene below.
+    /// By default, all events are consumed.
+    fn window_event(&mut self, ctx: &mut SceneContext<C>, event: &WindowEvent) -> bool {
+        true
+    }
+    /// Return false to let the event fall through to the scene below.
+    /// By default, all events are consumed.
+    fn device_event(
+        &mut self,
+        ctx: &mut SceneContext<C>,
+        device_id: DeviceId,
+        event: &DeviceEvent,
+    ) -> bool {
+        true
+    }
+}
+
+enum Transition<C> {
+    Push(Box<dyn Scene<C>>),
+    Pop,
+    Replace(Box<dyn Scene<C>>),
+}
+
+/// Handed to the [`Scene`] hooks. Derefs to the shared state.
+///
+/// Transitions are applied once the current hook returns, in the order they were requested.
+pub struct SceneContext<'a, C> {
+    shared: &'a mut C,
+    transitions: Vec<Transition<C>>,
+}
+
+impl<'a, C> SceneContext<'a, C> {
+    fn new(shared: &'a mut C) -> Self {
+        Self {
+            shared,
+            transitions: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, scene: impl Scene<C> + 'static) {
+        self.transitions.push(Transition::Push(Box::new(scene)));
+    }
+
+    pub fn pop(&mut self) {
+        self.transitions.push(Transition::Pop);
+    }
+
+    /// Pops the current top scene and pushes the new one, without resuming the one below.
+    pub fn replace(&mut self, scene: impl Scene<C> + 'static) {
+        self.transitions.push(Transition::Replace(Box::new(scene)));
+    }
+}
+
+impl<C> Deref for SceneContext<'_, C> {
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        self.shared
+    }
+}
+
+impl<C> DerefMut for SceneContext<'_, C> {
+    fn deref_mut(&mut self) -> &mut C {
+        self.shared
+    }
+}
+
+/// A stack of [`Scene`]s, feed it from the app's hooks.
+///
+/// Updates and events go to the top scene, events fall through to the scenes below
+/// as long as they are not consumed.
+///
+/// ```rust,no_run
+/// # use std::time::Duration;
+/// # use winit::event::WindowEvent;
+/// # use y_engine::{state::common::render::RenderCore, util::{input::InputManager,
+/// #     scene::{Scene, SceneContext, SceneStack}, time::FrameTime}};
+/// struct Shared {
+///     render_core: RenderCore,
+///     input: InputManager,
+/// }
+///
+/// struct Gameplay;
+/// struct Pause;
+///
+/// impl Scene<Shared> for Gameplay {
+///     fn window_event(&mut self, ctx: &mut SceneContext<Shared>, event: &WindowEvent) -> bool {
+///         // ... push Pause on escape ...
+/// #       ctx.push(Pause);
+///         true
+///     }
+/// }
+///
+/// impl Scene<Shared> for Pause {
+///     fn is_opaque(&self) -> bool {
+///         false
+///     }
+/// }
+///
+/// # struct MyApp { shared: Shared, scenes: SceneStack<Shared> }
+/// # impl MyApp {
+/// fn update(&mut self, dt: Duration) {
+///     self.scenes.update(&mut self.shared, dt);
+/// }
+/// fn window_redraw(&mut self, frame: &FrameTime) {
+///     self.scenes.render(&mut self.shared, frame);
+/// }
+/// # }
+/// ```
+pub struct SceneStack<C> {
+    scenes: Vec<Box<dyn Scene<C>>>,
+}
+
+impl<C> Default for SceneStack<C> {
+    fn default() -> Self {
+        Self { scenes: Vec::new() }
+    }
+}
+
+impl<C> SceneStack<C> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.scenes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+
+    pub fn push(&mut self, shared: &mut C, scene: impl Scene<C> + 'static) {
+        self.apply(shared, vec![Transition::Push(Box::new(scene))]);
+    }
+
+    pub fn pop(&mut self, shared: &mut C) {
+        self.apply(shared, vec![Transition::Pop]);
+    }
+
+    pub fn replace(&mut self, shared: &mut C, scene: impl Scene<C> + 'static) {
+        self.apply(shared, vec![Transition::Replace(Box::new(scene))]);
+    }
+
+    /// Exits all scenes, from the top to the bottom. The scenes below are not resumed.
+    pub fn clear(&mut self, shared: &mut C) {
+        let mut ctx = SceneContext::new(shared);
+        while let Some(mut scene) = self.scenes.pop() {
+            scene.exit(&mut ctx);
+        }
+        let transitions = ctx.transitions;
+        self.apply(shared, transitions);
+    }
+
+    pub fn fixed_update(&mut self, shared: &mut C, step: Duration) {
+        self.with_top(shared, |scene, ctx| scene.fixed_update(ctx, step));
+    }
+
+    pub fn update(&mut self, shared: &mut C, dt: Duration) {
+        self.with_top(shared, |scene, ctx| scene.update(ctx, dt));
+    }
+
+    pub fn render(&mut self, shared: &mut C, frame: &FrameTime) {
+        let first = self
+            .scenes
+            .iter()
+            .rposition(|scene| scene.is_opaque())
+            .unwrap_or(0);
+        let mut ctx = SceneContext::new(shared);
+        for scene in &mut self.scenes[first..] {
+            scene.render(&mut ctx, frame);
+        }
+        let transitions = ctx.transitions;
+        self.apply(shared, transitions);
+    }
+
+    /// Returns true if a scene consumed the event.
+    pub fn window_event(&mut self, shared: &mut C, event: &WindowEvent) -> bool {
+        self.dispatch(shared, |scene, ctx| scene.window_event(ctx, event))
+    }
+
+    /// Returns true if a scene consumed the event.
+    pub fn device_event(
+        &mut self,
+        shared: &mut C,
+        device_id: DeviceId,
+        event: &DeviceEvent,
+    ) -> bool {
+        self.dispatch(shared, |scene, ctx| {
+            scene.device_event(ctx, device_id, event)
+        })
+    }
+
+    fn with_top(
+        &mut self,
+        shared: &mut C,
+        f: impl FnOnce(&mut dyn Scene<C>, &mut SceneContext<C>),
+    ) {
+        let Some(scene) = self.scenes.last_mut() else {
+            return;
+        };
+        let mut ctx = SceneContext::new(shared);
+        f(scene.as_mut(), &mut ctx);
+        let transitions = ctx.transitions;
+        self.apply(shared, transitions);
+    }
+
+    /// Passes the event down from the top scene until one consumes it.
+    fn dispatch(
+        &mut self,
+        shared: &mut C,
+        mut f: impl FnMut(&mut dyn Scene<C>, &mut SceneContext<C>) -> bool,
+    ) -> bool {
+        let mut ctx = SceneContext::new(shared);
+        let consumed = self
+            .scenes
+            .iter_mut()
+            .rev()
+            .any(|scene| f(scene.as_mut(), &mut ctx));
+        let transitions = ctx.transitions;
+        self.apply(shared, transitions);
+        consumed
+    }
+
+    /// Applies the transitions, and the ones requested by the enter/exit/pause/resume hooks.
+    fn apply(&mut self, shared: &mut C, transitions: Vec<Transition<C>>) {
+        let mut ctx = SceneContext::new(shared);
+        let mut pending = transitions;
+        while !pending.is_empty() {
+            for transition in pending {
+                match transition {
+                    Transition::Push(mut scene) => {
+                        if let Some(top) = self.scenes.last_mut() {
+                            top.pause(&mut ctx);
+                        }
+                        scene.enter(&mut ctx);
+                        self.scenes.push(scene);
+                    }
+                    Transition::Pop => {
+                        if let Some(mut scene) = self.scenes.pop() {
+                            scene.exit(&mut ctx);
+                        }
+                        if let Some(top) = self.scenes.last_mut() {
+                            top.resume(&mut ctx);
+                        }
+                    }
+                    Transition::Replace(mut scene) => {
+                        if let Some(mut old) = self.scenes.pop() {
+                            old.exit(&mut ctx);
+                        }
+                        scene.enter(&mut ctx);
+                        self.scenes.push(scene);
+                    }
+                }
+            }
+            pending = std::mem::take(&mut ctx.transitions);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Log {
+        calls: Vec<String>,
+    }
+
+    /// Logs its hooks, and can push another scene when it is entered.
+    struct Recorder {
+        name: &'static str,
+        opaque: bool,
+        consumes: bool,
+        on_enter: Option<fn(&mut SceneContext<Log>)>,
+    }
+
+    impl Recorder {
+        fn new(name: &'static str) -> Self {
+            Self {
+                name,
+                opaque: true,
+                consumes: true,
+                on_enter: None,
+            }
+        }
+
+        fn log(&self, ctx: &mut SceneContext<Log>, hook: &str) {
+            ctx.calls.push(format!("{} {hook}", self.name));
+        }
+    }
+
+    impl Scene<Log> for Recorder {
+        fn enter(&mut self, ctx: &mut SceneContext<Log>) {
+            self.log(ctx, "enter");
+            if let Some(on_enter) = self.on_enter {
+                on_enter(ctx);
+            }
+        }
+
+        fn exit(&mut self, ctx: &mut SceneContext<Log>) {
+            self.log(ctx, "exit");
+        }
+
+        fn pause(&mut self, ctx: &mut SceneContext<Log>) {
+            self.log(ctx, "pause");
+        }
+
+        fn resume(&mut self, ctx: &mut SceneContext<Log>) {
+            self.log(ctx, "resume");
+        }
+
+        fn update(&mut self, ctx: &mut SceneContext<Log>, _: Duration) {
+            self.log(ctx, "update");
+        }
+
+        fn render(&mut self, ctx: &mut SceneContext<Log>, _: &FrameTime) {
+            self.log(ctx, "render");
+        }
+
+        fn is_opaque(&self) -> bool {
+            self.opaque
+        }
+
+        fn window_event(&mut self, ctx: &mut SceneContext<Log>, _: &WindowEvent) -> bool {
+            self.log(ctx, "event");
+            self.consumes
+        }
+    }
+
+    fn take_calls(log: &mut Log) -> Vec<String> {
+        std::mem::take(&mut log.calls)
+    }
+
+    fn frame_time() -> FrameTime {
+        FrameTime {
+            delta: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            frame: 0,
+            alpha: 0.0,
+            stats: Default::default(),
+        }
+    }
+
+    #[test]
+    fn push_pauses_and_pop_resumes() {
+        let mut log = Log::default();
+        let mut scenes = SceneStack::new();
+        scenes.push(&mut log, Recorder::new("a"));
+        scenes.push(&mut log, Recorder::new("b"));
+        scenes.pop(&mut log);
+        assert_eq!(
+            take_calls(&mut log),
+            ["a enter", "a pause", "b enter", "b exit", "a resume"]
+        );
+        assert_eq!(scenes.len(), 1);
+    }
+
+    #[test]
+    fn replace_does_not_resume_the_scene_below() {
+        let mut log = Log::default();
+        let mut scenes = SceneStack::new();
+        scenes.push(&mut log, Recorder::new("a"));
+        scenes.push(&mut log, Recorder::new("b"));
+        take_calls(&mut log);
+        scenes.replace(&mut log, Recorder::new("c"));
+        assert_eq!(take_calls(&mut log), ["b exit", "c enter"]);
+        assert_eq!(scenes.len(), 2);
+
+        scenes.update(&mut log, Duration::ZERO);
+        assert_eq!(take_calls(&mut log), ["c update"]);
+    }
+
+    #[test]
+    fn transitions_requested_by_hooks_are_applied() {
+        fn loading() -> Recorder {
+            Recorder {
+                on_enter: Some(|ctx| ctx.replace(Recorder::new("menu"))),
+                ..Recorder::new("loading")
+            }
+        }
+
+        let mut log = Log::default();
+        let mut scenes = SceneStack::new();
+        let splash = Recorder {
+            on_enter: Some(|ctx| ctx.push(loading())),
+            ..Recorder::new("splash")
+        };
+        scenes.push(&mut log, splash);
+        assert_eq!(
+            take_calls(&mut log),
+            [
+                "splash enter",
+                "splash pause",
+                "loading enter",
+                "loading exit",
+                "menu enter"
+            ]
+        );
+        assert_eq!(scenes.len(), 2);
+    }
+
+    #[test]
+    fn clear_exits_from_the_top_without_resuming() {
+        let mut log = Log::default();
+        let mut scenes = SceneStack::new();
+        scenes.push(&mut log, Recorder::new("a"));
+        scenes.push(&mut log, Recorder::new("b"));
+        take_calls(&mut log);
+        scenes.clear(&mut log);
+        assert_eq!(take_calls(&mut log), ["b exit", "a exit"]);
+        assert!(scenes.is_empty());
+    }
+
+    #[test]
+    fn events_fall_through_until_consumed() {
+        let mut log = Log::default();
+        let mut scenes = SceneStack::new();
+        scenes.push(&mut log, Recorder::new("a"));
+        for name in ["b", "c"] {
+            let overlay = Recorder {
+                consumes: false,
+                ..Recorder::new(name)
+            };
+            scenes.push(&mut log, overlay);
+        }
+        take_calls(&mut log);
+
+        assert!(scenes.window_event(&mut log, &WindowEvent::Focused(true)));
+        assert_eq!(take_calls(&mut log), ["c event", "b event", "a event"]);
+
+        scenes.clear(&mut log);
+        take_calls(&mut log);
+        assert!(!scenes.window_event(&mut log, &WindowEvent::Focused(true)));
+        assert!(take_calls(&mut log).is_empty());
+
+        let overlay = Recorder {
+            consumes: false,
+            ..Recorder::new("e")
+        };
+        scenes.push(&mut log, overlay);
+        take_calls(&mut log);
+        assert!(!scenes.window_event(&mut log, &WindowEvent::Focused(true)));
+        assert_eq!(take_calls(&mut log), ["e event"]);
+    }
+
+    #[test]
+    fn render_starts_at_the_topmost_opaque_scene() {
+        let mut log = Log::default();
+        let mut scenes = SceneStack::new();
+        scenes.push(&mut log, Recorder::new("a"));
+        scenes.push(&mut log, Recorder::new("b"));
+        let overlay = Recorder {
+            opaque: false,
+            ..Recorder::new("c")
+        };
+        scenes.push(&mut log, overlay);
+        take_calls(&mut log);
+        scenes.render(&mut log, &frame_time());
+        assert_eq!(take_calls(&mut log), ["b render", "c render"]);
+    }
+}
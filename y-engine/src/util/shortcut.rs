@@ -0,0 +1,385 @@
+use std::{error::Error, fmt, str::FromStr};
+
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use winit::{
+    event::ElementState,
+    keyboard::{Key, KeyCode, ModifiersState},
+};
+
+use super::{action::Binding, input::KeyInput};
+
+/// The modifier names in the order they are written, with their aliases.
+const MODIFIERS: [(ModifiersState, &[&str]); 4] = [
+    (ModifiersState::CONTROL, &["Ctrl", "Control"]),
+    (ModifiersState::SHIFT, &["Shift"]),
+    (ModifiersState::ALT, &["Alt", "Option"]),
+    (ModifiersState::SUPER, &["Super", "Cmd", "Meta"]),
+];
+
+/// The key of a [`Shortcut`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ShortcutKey {
+    /// Character keys are matched case-insensitively, as Shift changes them.
+    Key(Key),
+    /// Matches the key at this position on the keyboard, regardless of the layout.
+    PhysicalKey(KeyCode),
+}
+
+/// A key pressed while holding exactly the given modifiers, e.g. `Ctrl+Shift+S`.
+///
+/// Shortcuts are parsed from and written as text: the modifiers (`Ctrl`, `Shift`, `Alt`,
+/// `Super`) followed by the key, joined with `+`. The key is written like a key [`Binding`],
+/// so `Ctrl+Physical:KeyZ` matches the key where Z is on a QWERTY keyboard.
+///
+/// With Shift held, keys like `1` produce another character (`!` on US layouts),
+/// use physical keys for those.
+///
+/// ```rust
+/// # use winit::keyboard::{Key, ModifiersState};
+/// # use y_engine::util::shortcut::{Shortcut, ShortcutKey};
+/// let save_as: Shortcut = "Ctrl+Shift+S".parse().unwrap();
+/// assert_eq!(save_as.modifiers(), ModifiersState::CONTROL | ModifiersState::SHIFT);
+/// assert_eq!(save_as.key(), &ShortcutKey::Key(Key::Character("s".into())));
+/// assert_eq!(save_as.to_string(), "Ctrl+Shift+S");
+/// assert!("Ctrl+".parse::<Shortcut>().is_err());
+///
+/// let sharp_s: Shortcut = "Ctrl+ß".parse().unwrap();
+/// assert_eq!(sharp_s.to_string().parse::<Shortcut>().unwrap(), sharp_s);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Shortcut {
+    modifiers: ModifiersState,
+    key: ShortcutKey,
+}
+
+impl Shortcut {
+    pub fn new(modifiers: ModifiersState, key: ShortcutKey) -> Self {
+        let key = match key {
+            ShortcutKey::Key(key) => ShortcutKey::Key(normalize(key)),
+            key => key,
+        };
+        Self { modifiers, key }
+    }
+
+    pub fn modifiers(&self) -> ModifiersState {
+        self.modifiers
+    }
+
+    /// Character keys are lowercase.
+    pub fn key(&self) -> &ShortcutKey {
+        &self.key
+    }
+
+    /// True if the key was pressed (or repeated) while exactly these modifiers were held.
+    pub fn matches(&self, input: &KeyInput, modifiers: ModifiersState) -> bool {
+        if input.state != ElementState::Pressed || modifiers != self.modifiers {
+            return false;
+        }
+        match &self.key {
+            ShortcutKey::Key(key) => *key == normalize(input.logical_key.clone()),
+            ShortcutKey::PhysicalKey(key) => input.key_code() == Some(*key),
+        }
+    }
+}
+
+/// Lowercases character keys, so Shift does not change them. Characters that lowercase
+/// to more than one ("İ" to "i̇") are kept as they are, as they would not parse.
+fn normalize(key: Key) -> Key {
+    match key {
+        Key::Character(character) => {
+            let lower = character.to_lowercase();
+            if lower.chars().count() == 1 {
+                Key::Character(lower.into())
+            } else {
+                Key::Character(character)
+            }
+        }
+        key => key,
+    }
+}
+
+/// Runs callbacks when their [`Shortcut`] is pressed, feed it from
+/// [`crate::app::App::keyboard_button_input`].
+///
+/// Like [`crate::util::scene::SceneStack`], it hands the callbacks the shared state `C`.
+///
+/// ```rust,no_run
+/// # use winit::event::Modifiers;
+/// # use y_engine::util::{input::{InputManager, KeyInput}, shortcut::ShortcutMap};
+/// struct Editor {
+///     input: InputManager,
+///     dirty: bool,
+/// }
+///
+/// let mut shortcuts: ShortcutMap<Editor> = ShortcutMap::new();
+/// shortcuts.register("Ctrl+S".parse().unwrap(), |editor| editor.dirty = false);
+///
+/// # struct MyApp { editor: Editor, shortcuts: ShortcutMap<Editor> }
+/// # impl MyApp {
+/// fn modifiers_changed(&mut self, modifiers: Modifiers) {
+///     self.editor.input.handle_modifiers_changed(modifiers);
+/// }
+/// fn keyboard_button_input(&mut self, input: KeyInput) {
+///     let modifiers = self.editor.input.modifiers();
+///     if !self.shortcuts.handle_keyboard_button_input(&mut self.editor, &input, modifiers) {
+///         self.editor.input.handle_keyboard_button_input(input);
+///     }
+/// }
+/// # }
+/// ```
+pub struct ShortcutMap<C> {
+    callbacks: FxHashMap<Shortcut, Callback<C>>,
+}
+
+type Callback<C> = Box<dyn FnMut(&mut C)>;
+
+impl<C> Default for ShortcutMap<C> {
+    fn default() -> Self {
+        Self {
+            callbacks: FxHashMap::default(),
+        }
+    }
+}
+
+impl<C> ShortcutMap<C> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the callback if the shortcut is already registered.
+    pub fn register(&mut self, shortcut: Shortcut, callback: impl FnMut(&mut C) + 'static) {
+        self.callbacks.insert(shortcut, Box::new(callback));
+    }
+
+    /// Returns false if the shortcut was not registered.
+    pub fn unregister(&mut self, shortcut: &Shortcut) -> bool {
+        self.callbacks.remove(shortcut).is_some()
+    }
+
+    pub fn is_registered(&self, shortcut: &Shortcut) -> bool {
+        self.callbacks.contains_key(shortcut)
+    }
+
+    /// In no particular order.
+    pub fn shortcuts(&self) -> impl Iterator<Item = &Shortcut> {
+        self.callbacks.keys()
+    }
+
+    /// Runs the callback of the shortcut that was pressed, key repeats are ignored.
+    /// If both a logical and a physical shortcut match, only the logical one runs.
+    ///
+    /// Returns true if a callback ran.
+    pub fn handle_keyboard_button_input(
+        &mut self,
+        shared: &mut C,
+        input: &KeyInput,
+        modifiers: ModifiersState,
+    ) -> bool {
+        if input.state != ElementState::Pressed || input.repeat {
+            return false;
+        }
+        let logical = Shortcut::new(modifiers, ShortcutKey::Key(input.logical_key.clone()));
+        let physical = input
+            .key_code()
+            .map(|key| Shortcut::new(modifiers, ShortcutKey::PhysicalKey(key)));
+        let callback = match self.callbacks.get_mut(&logical) {
+            Some(callback) => callback,
+            None => match physical.and_then(|physical| self.callbacks.get_mut(&physical)) {
+                Some(callback) => callback,
+                None => return false,
+            },
+        };
+        callback(shared);
+        true
+    }
+}
+
+impl fmt::Display for Shortcut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, names) in MODIFIERS {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{}+", names[0])?;
+            }
+        }
+        match &self.key {
+            // Only uppercase if that parses back to the same key, "ß" uppercases to "SS"
+            // and "ς" to "Σ", which lowercases to "σ".
+            ShortcutKey::Key(Key::Character(character)) => {
+                let upper = character.to_uppercase();
+                let parses_back = upper.chars().count() == 1
+                    && normalize(Key::Character(upper.as_str().into()))
+                        == Key::Character(character.clone());
+                if parses_back {
+                    f.write_str(&upper)
+                } else {
+                    f.write_str(character)
+                }
+            }
+            ShortcutKey::Key(key) => write!(f, "{}", Binding::Key(key.clone())),
+            ShortcutKey::PhysicalKey(key) => write!(f, "{}", Binding::PhysicalKey(*key)),
+        }
+    }
+}
+
+impl FromStr for Shortcut {
+    type Err = ParseShortcutError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseShortcutError(s.to_string());
+        let mut modifiers = ModifiersState::empty();
+        let mut rest = s;
+        // Split off the modifiers one by one, so `Ctrl++` is Ctrl and the plus key.
+        'modifiers: while let Some((name, key)) = rest.split_once('+') {
+            for (modifier, names) in MODIFIERS {
+                if names.iter().any(|alias| alias.eq_ignore_ascii_case(name)) {
+                    modifiers |= modifier;
+                    rest = key;
+                    continue 'modifiers;
+                }
+            }
+            break;
+        }
+        let key = match rest.parse().map_err(|_| error())? {
+            Binding::Key(Key::Character(character)) if character.chars().count() != 1 => {
+                return Err(error())
+            }
+            Binding::Key(key) => ShortcutKey::Key(key),
+            Binding::PhysicalKey(key) => ShortcutKey::PhysicalKey(key),
+            Binding::MouseButton(_) | Binding::Wheel(_) => return Err(error()),
+        };
+        Ok(Self::new(modifiers, key))
+    }
+}
+
+impl Serialize for Shortcut {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Shortcut {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// The text is not a valid [`Shortcut`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseShortcutError(pub String);
+
+impl fmt::Display for ParseShortcutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid shortcut: {:?}", self.0)
+    }
+}
+
+impl Error for ParseShortcutError {}
+
+#[cfg(test)]
+mod tests {
+    use winit::keyboard::{KeyLocation, PhysicalKey};
+
+    use super::*;
+
+    fn key_input(code: KeyCode, logical_key: &str) -> KeyInput {
+        KeyInput {
+            physical_key: PhysicalKey::Code(code),
+            logical_key: Key::Character(logical_key.into()),
+            text: Some(logical_key.into()),
+            location: KeyLocation::Standard,
+            state: ElementState::Pressed,
+            repeat: false,
+        }
+    }
+
+    #[test]
+    fn text_round_trips() {
+        for text in [
+            "Ctrl+S",
+            "Shift+Ctrl+s",
+            "Ctrl+ß",
+            "Ctrl+İ",
+            "Ctrl+ı",
+            "Alt+Σ",
+            "Alt+ς",
+            "Ctrl++",
+            "+",
+            "Super+F1",
+            "Ctrl+Shift+Physical:KeyZ",
+            "Cmd+Physical:Digit1",
+        ] {
+            let shortcut: Shortcut = text.parse().unwrap();
+            let written = shortcut.to_string();
+            let parsed: Shortcut = written.parse().unwrap_or_else(|error| {
+                panic!("{text:?} was written as {written:?}, which fails to parse: {error}")
+            });
+            assert_eq!(parsed, shortcut, "{text:?} was written as {written:?}");
+            assert_eq!(parsed.to_string(), written);
+        }
+    }
+
+    #[test]
+    fn invalid_text_is_rejected() {
+        for text in ["", "Ctrl+", "Ctrl+ab", "Ctrl+Mouse:Left", "Wheel:Up"] {
+            assert!(text.parse::<Shortcut>().is_err(), "{text:?} parsed");
+        }
+    }
+
+    #[test]
+    fn character_keys_match_case_insensitively() {
+        let save_as: Shortcut = "Ctrl+Shift+S".parse().unwrap();
+        let modifiers = ModifiersState::CONTROL | ModifiersState::SHIFT;
+        assert!(save_as.matches(&key_input(KeyCode::KeyS, "S"), modifiers));
+        assert!(save_as.matches(&key_input(KeyCode::KeyS, "s"), modifiers));
+        assert!(!save_as.matches(&key_input(KeyCode::KeyS, "S"), ModifiersState::CONTROL));
+
+        let dotless: Shortcut = "Ctrl+I".parse().unwrap();
+        assert!(dotless.matches(&key_input(KeyCode::KeyI, "i"), ModifiersState::CONTROL));
+        let dotted: Shortcut = "Ctrl+İ".parse().unwrap();
+        assert!(!dotted.matches(&key_input(KeyCode::KeyI, "i"), ModifiersState::CONTROL));
+        assert!(dotted.matches(&key_input(KeyCode::KeyI, "İ"), ModifiersState::CONTROL));
+    }
+
+    #[test]
+    fn map_prefers_logical_shortcuts_and_ignores_repeats() {
+        let mut shortcuts: ShortcutMap<Vec<&str>> = ShortcutMap::new();
+        shortcuts.register("Ctrl+Z".parse().unwrap(), |calls| calls.push("logical"));
+        shortcuts.register("Ctrl+Physical:KeyZ".parse().unwrap(), |calls| {
+            calls.push("physical")
+        });
+        let mut calls = Vec::new();
+
+        // QWERTY: both match.
+        let input = key_input(KeyCode::KeyZ, "z");
+        assert!(shortcuts.handle_keyboard_button_input(
+            &mut calls,
+            &input,
+            ModifiersState::CONTROL
+        ));
+        // QWERTZ: the Z key is where Y is on QWERTY.
+        let input = key_input(KeyCode::KeyZ, "y");
+        assert!(shortcuts.handle_keyboard_button_input(
+            &mut calls,
+            &input,
+            ModifiersState::CONTROL
+        ));
+        let repeat = KeyInput {
+            repeat: true,
+            ..key_input(KeyCode::KeyZ, "z")
+        };
+        assert!(!shortcuts.handle_keyboard_button_input(
+            &mut calls,
+            &repeat,
+            ModifiersState::CONTROL
+        ));
+        assert!(!shortcuts.handle_keyboard_button_input(
+            &mut calls,
+            &input,
+            ModifiersState::empty()
+        ));
+
+        assert_eq!(calls, ["logical", "physical"]);
+    }
+}
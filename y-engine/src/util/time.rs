@@ -0,0 +1,224 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// The last part of a limited frame's wait is spun instead of slept, sleeping is not that precise.
+const SPIN_TIME: Duration = Duration::from_millis(2);
+
+/// Timing of the frame that is about to be rendered.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameTime {
+    /// Time since the previous frame.
+    pub delta: Duration,
+    /// Time since the app started running.
+    pub elapsed: Duration,
+    /// Index of this frame, starting at 0.
+    pub frame: u64,
+    /// How far this frame is between the last and the next fixed update, from 0.0 to 1.0.
+    /// Use it to interpolate between the last two simulation states.
+    pub alpha: f32,
+    /// Statistics of the last frames, including this one.
+    pub stats: FrameStats,
+}
+
+/// Frame time statistics over the last frames, see [`crate::config::LoopConfig::stats_frames`].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FrameStats {
+    /// Number of frames the statistics are over.
+    pub frames: usize,
+    pub average: Duration,
+    pub min: Duration,
+    pub max: Duration,
+    /// 99% of the frames were at least this fast.
+    pub p99: Duration,
+    /// Zero if the frames took no time.
+    pub fps: f64,
+}
+
+/// Measures the time between frames and keeps statistics over the last `history` frames.
+///
+/// Takes the current time as an argument, so it can be driven by a fake clock.
+///
+/// ```rust
+/// # use std::time::{Duration, Instant};
+/// # use y_engine::util::time::FrameClock;
+/// let start = Instant::now();
+/// let mut clock = FrameClock::new(start, 120);
+/// clock.tick(start + Duration::from_millis(10));
+/// let frame = clock.tick(start + Duration::from_millis(30));
+/// assert_eq!(frame.delta, Duration::from_millis(20));
+/// assert_eq!(frame.frame, 1);
+/// assert_eq!(frame.stats.average, Duration::from_millis(15));
+/// ```
+#[derive(Debug, Clone)]
+pub struct FrameClock {
+    start: Instant,
+    last: Instant,
+    frame: u64,
+    history: VecDeque<Duration>,
+    history_len: usize,
+}
+
+impl FrameClock {
+    pub fn new(start: Instant, history: usize) -> Self {
+        Self {
+            start,
+            last: start,
+            frame: 0,
+            history: VecDeque::with_capacity(history),
+            history_len: history.max(1),
+        }
+    }
+
+    /// Starts a new frame at `now`.
+    pub fn tick(&mut self, now: Instant) -> FrameTime {
+        let delta = now.saturating_duration_since(self.last);
+        if self.history.len() == self.history_len {
+            self.history.pop_front();
+        }
+        self.history.push_back(delta);
+        let frame_time = FrameTime {
+            delta,
+            elapsed: now.saturating_duration_since(self.start),
+            frame: self.frame,
+            alpha: 0.0,
+            stats: self.stats(),
+        };
+        self.last = now;
+        self.frame += 1;
+        frame_time
+    }
+
+    pub fn stats(&self) -> FrameStats {
+        if self.history.is_empty() {
+            return FrameStats::default();
+        }
+        let mut sorted: Vec<_> = self.history.iter().copied().collect();
+        sorted.sort_unstable();
+        let total: Duration = sorted.iter().sum();
+        let frames = sorted.len();
+        FrameStats {
+            frames,
+            average: total / frames as u32,
+            min: sorted[0],
+            max: sorted[frames - 1],
+            p99: sorted[(frames * 99).div_ceil(100) - 1],
+            // Zero for frames that took no time at all (fake clocks), instead of infinity.
+            fps: if total.is_zero() {
+                0.0
+            } else {
+                frames as f64 / total.as_secs_f64()
+            },
+        }
+    }
+
+    /// Start of the last frame.
+    pub fn last_tick(&self) -> Instant {
+        self.last
+    }
+}
+
+/// Accumulates frame time and hands it out in fixed steps.
+///
+/// ```rust
+/// # use std::time::Duration;
+/// # use y_engine::util::time::FixedTimestep;
+/// let mut timestep = FixedTimestep::new(Duration::from_millis(10), 4);
+/// assert_eq!(timestep.advance(Duration::from_millis(25)), 2);
+/// assert_eq!(timestep.alpha(), 0.5);
+/// // Long frames are capped, the rest of the time is dropped.
+/// assert_eq!(timestep.advance(Duration::from_secs(1)), 4);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FixedTimestep {
+    step: Duration,
+    max_steps: u32,
+    accumulator: Duration,
+}
+
+impl FixedTimestep {
+    /// `max_steps` is the most steps a single frame may run, so slow frames can not
+    /// cause ever more steps (and ever slower frames). A zero step is treated as 1ns,
+    /// and zero `max_steps` as 1.
+    pub fn new(step: Duration, max_steps: u32) -> Self {
+        Self {
+            step: step.max(Duration::from_nanos(1)),
+            max_steps: max_steps.max(1),
+            accumulator: Duration::ZERO,
+        }
+    }
+
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    /// Adds the time of a frame and returns how many fixed steps to run for it.
+    pub fn advance(&mut self, delta: Duration) -> u32 {
+        self.accumulator += delta;
+        let mut steps = 0;
+        while self.accumulator >= self.step {
+            if steps == self.max_steps {
+                // Drop the time that can not be caught up, but keep the progress into the next step.
+                self.accumulator = Duration::from_nanos(
+                    (self.accumulator.as_nanos() % self.step.as_nanos()) as u64,
+                );
+                break;
+            }
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+        steps
+    }
+
+    /// Progress into the next step, from 0.0 to 1.0.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator.as_secs_f64() / self.step.as_secs_f64()) as f32
+    }
+}
+
+/// Caps the frame rate by blocking until the next frame is due.
+///
+/// Sleeps for most of the wait and spins for the rest, which is much more precise than
+/// sleeping alone (e.g. waiting for the event loop's `ControlFlow::WaitUntil`).
+#[derive(Debug, Clone)]
+pub struct FrameLimiter {
+    frame_time: Duration,
+    next_frame: Option<Instant>,
+}
+
+impl FrameLimiter {
+    pub fn new(fps: f64) -> Self {
+        Self {
+            frame_time: frame_duration(fps),
+            next_frame: None,
+        }
+    }
+
+    /// Blocks until the next frame is due and returns the current time.
+    pub fn wait(&mut self) -> Instant {
+        let mut now = Instant::now();
+        if let Some(next_frame) = self.next_frame {
+            if let Some(remaining) = next_frame.checked_duration_since(now) {
+                if remaining > SPIN_TIME {
+                    std::thread::sleep(remaining - SPIN_TIME);
+                }
+                while Instant::now() < next_frame {
+                    std::hint::spin_loop();
+                }
+                now = Instant::now();
+            }
+        }
+        // Keep a steady pace, unless the last frame took so long that the next one is already due.
+        self.next_frame = Some(match self.next_frame {
+            Some(next_frame) if now < next_frame + self.frame_time => next_frame + self.frame_time,
+            _ => now + self.frame_time,
+        });
+        now
+    }
+}
+
+/// Invalid or unlimited frame rates result in no waiting at all.
+pub(crate) fn frame_duration(fps: f64) -> Duration {
+    Duration::try_from_secs_f64(1.0 / fps).unwrap_or(Duration::ZERO)
+}